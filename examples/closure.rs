@@ -6,7 +6,7 @@ async fn main() {
     pretty_env_logger::init(); // For logging purpose only.
 
     // 1. Use a closure to subscribe directly to the event
-    HeartBeatLogged::subscribe_fn(|event| {
+    let _handle = HeartBeatLogged::subscribe_fn(|event| {
         Box::pin(async move { println!("'subscribe_with' handler: {:?}", event.data()) })
     })
     .await;
@@ -6,10 +6,10 @@ async fn main() {
     pretty_env_logger::init(); // For logging purpose only.
 
     // 1. Subscribe directly to the event using the event's static `subscribe` method
-    UserCreated::subscribe::<HandleUserCreated>().await;
+    let _handle = UserCreated::subscribe::<HandleUserCreated>().await;
 
     // 1.b You can subscribe with an instance of your handler
-    UserCreated::subscribe_with(HandleUserCreated).await;
+    let _handle2 = UserCreated::subscribe_with(HandleUserCreated).await;
 
     // 2. Somewhere in your code create an instance of your event is dispatch it
     let event = UserCreated { id: 33 };
@@ -0,0 +1,38 @@
+use orsomafo::{handler_fn, Dispatchable, EventDispatcherBuilder, EventHandler, Lifetime};
+
+#[tokio::main]
+async fn main() {
+    pretty_env_logger::init(); // For logging purpose only.
+
+    let dispatcher = EventDispatcherBuilder::new()
+        .before_dispatch(|event| println!("dispatching: {}", event.name_ref()))
+        .before_handle(|event, handler| println!("{} is handling {}", handler, event.name_ref()))
+        .after_handle(|_, handler, took| println!("{} took {:?}", handler, took))
+        .on_error(|_, handler, error| println!("{} failed: {}", handler, error))
+        .on_handler_removed(|handler| println!("{} was removed", handler))
+        .build()
+        .await;
+
+    let _welcome =
+        UserCreated::subscribe_with(handler_fn(|_| Box::pin(async {})).with_label("welcome")).await;
+    let _mailer = UserCreated::subscribe_with(
+        handler_fn(|_| Box::pin(async { panic!("mail server is down") })).with_label("mailer"),
+    )
+    .await;
+    let _once = UserCreated::subscribe_with(
+        handler_fn(|_| Box::pin(async {}))
+            .with_label("once")
+            .with_lifetime(Lifetime::Once),
+    )
+    .await;
+
+    let report = dispatcher.dispatch_and_wait(UserCreated { id: 1 }).await;
+    println!("failed handlers: {:?}", report.failed());
+}
+
+#[derive(Clone, serde::Deserialize, serde::Serialize)]
+struct UserCreated {
+    id: u32,
+}
+
+impl Dispatchable for UserCreated {}
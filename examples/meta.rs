@@ -1,16 +1,23 @@
 use async_trait::async_trait;
-use orsomafo::{Dispatchable, DispatchedEvent, EventHandler};
+use orsomafo::{event_dispatcher, Dispatchable, DispatchedEvent, EventHandler};
 use tokio::time::{sleep, Duration};
 
 #[tokio::main]
 async fn main() {
     pretty_env_logger::init(); // For logging purpose only.
 
-    UserCreated::subscribe::<HandleUserCreated>().await;
+    let _handle = UserCreated::subscribe::<HandleUserCreated>().await;
 
     let event = UserCreated { id: 34343464 };
     event.dispatch_event();
 
+    // Metadata can be attached to the event as headers
+    event_dispatcher()
+        .event(UserCreated { id: 34343465 })
+        .header("tenant", "acme")
+        .header("source", "signup-service")
+        .dispatch();
+
     // The following line is use to pause the application for
     // few milliseconds. This will allow us to handle all dispatched events.
     // In a full application, this line wil not be require.
@@ -34,6 +41,11 @@ impl EventHandler for HandleUserCreated {
         println!("name: {:?}", dispatched.name_ref());
         println!("created at: {:?}", dispatched.created_at());
         println!("created at timestamp: {:?}", dispatched.created_at_ts());
+        println!("dispatched at: {:?}", dispatched.dispatched_at());
+        println!("handled at: {:?}", dispatched.handled_at());
         println!("data: {:?}", dispatched.data());
+        println!("headers: {:?}", dispatched.headers());
+        println!("correlation id: {:?}", dispatched.correlation_id());
+        println!("causation id: {:?}", dispatched.causation_id());
     }
 }
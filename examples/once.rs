@@ -6,7 +6,7 @@ use tokio::time::{sleep, Duration};
 async fn main() {
     pretty_env_logger::init(); // For logging purpose only.
 
-    UserCreated::subscribe::<HandleUserCreated>().await;
+    let _handle = UserCreated::subscribe::<HandleUserCreated>().await;
 
     let event = UserCreated { id: 1 };
     event.dispatch_event();
@@ -0,0 +1,67 @@
+use async_trait::async_trait;
+use orsomafo::{Dispatchable, DispatchedEvent, EventHandler};
+use tokio::time::{sleep, Duration};
+
+#[tokio::main]
+async fn main() {
+    pretty_env_logger::init(); // For logging purpose only.
+
+    // 1. Handlers with a higher priority are called first
+    let _handle1 = OrderPlaced::subscribe_with(SendReceipt.with_priority(-10)).await;
+    let _handle2 = OrderPlaced::subscribe_with(ValidateOrder.with_priority(100)).await;
+
+    // 2. Handlers can also be ordered relative to each other by label or name
+    let _handle3 =
+        OrderPlaced::subscribe_with(ReserveStock.with_label("stock").run_after("validate")).await;
+
+    // 3. A handler that would create a cycle is rejected
+    let result =
+        OrderPlaced::try_subscribe_with(ValidateOrder.with_label("validate").run_after("stock"))
+            .await;
+    println!("registering a cycle: {:?}", result.err());
+
+    OrderPlaced { id: 1 }.dispatch_event();
+
+    // The following line is use to pause the application for
+    // few milliseconds. This will allow us to handle all dispatched events.
+    // In a full application, this line will not be require.
+    sleep(Duration::from_millis(100)).await;
+}
+
+#[derive(Clone, serde::Deserialize, serde::Serialize)]
+struct OrderPlaced {
+    id: u32,
+}
+
+impl Dispatchable for OrderPlaced {}
+
+struct ValidateOrder;
+
+#[async_trait]
+impl EventHandler for ValidateOrder {
+    async fn handle(&self, dispatched: DispatchedEvent) {
+        println!("1. validating order: {}", dispatched.id());
+    }
+
+    fn label(&self) -> Option<String> {
+        Some("validate".to_string())
+    }
+}
+
+struct ReserveStock;
+
+#[async_trait]
+impl EventHandler for ReserveStock {
+    async fn handle(&self, dispatched: DispatchedEvent) {
+        println!("2. reserving stock for order: {}", dispatched.id());
+    }
+}
+
+struct SendReceipt;
+
+#[async_trait]
+impl EventHandler for SendReceipt {
+    async fn handle(&self, dispatched: DispatchedEvent) {
+        println!("3. sending receipt for order: {}", dispatched.id());
+    }
+}
@@ -1,17 +1,26 @@
 use async_trait::async_trait;
-use orsomafo::{Dispatchable, DispatchedEvent, EventHandler};
+use orsomafo::{event_dispatcher, Dispatchable, DispatchedEvent, EventHandler, Outcome};
 use tokio::time::{sleep, Duration};
 
 #[tokio::main]
 async fn main() {
     pretty_env_logger::init(); // For logging purpose only.
 
-    UserCreated::subscribe::<HandleUserCreated>().await;
-    UserCreated::subscribe::<HandleUserCreated2>().await; // This handler will never get called
+    let _handle = UserCreated::subscribe::<HandleUserCreated>().await;
+    let _handle2 = UserCreated::subscribe::<HandleUserCreated2>().await; // This handler will never get called
 
     let event = UserCreated { id: 8701 };
     event.dispatch_event();
 
+    // A handler can also decide per event whether the event should propagate
+    let _handle3 = OrderPlaced::subscribe::<ValidateOrder>().await;
+    let _handle4 = OrderPlaced::subscribe::<ShipOrder>().await;
+
+    let report = event_dispatcher()
+        .dispatch_and_wait(OrderPlaced { quantity: 0 })
+        .await;
+    println!("propagation stopped by: {:?}", report.stopped_by());
+
     // The following line is use to pause the application for
     // few milliseconds. This will allow us to handle all dispatched events.
     // In a full application, this line will not be require.
@@ -50,3 +59,41 @@ impl EventHandler for HandleUserCreated2 {
         println!("Handling event with ID: {}", dispatched.id());
     }
 }
+
+#[derive(Clone, serde::Deserialize, serde::Serialize)]
+struct OrderPlaced {
+    quantity: u32,
+}
+
+impl Dispatchable for OrderPlaced {}
+
+#[derive(Default)]
+struct ValidateOrder;
+
+#[async_trait]
+impl EventHandler for ValidateOrder {
+    async fn handle(&self, dispatched: DispatchedEvent) {
+        self.handle_event(dispatched).await;
+    }
+
+    async fn handle_event(&self, dispatched: DispatchedEvent) -> Outcome {
+        let order: OrderPlaced = dispatched.the_event().unwrap();
+
+        // Orders without items are not shipped
+        if order.quantity == 0 {
+            Outcome::Stop
+        } else {
+            Outcome::Continue
+        }
+    }
+}
+
+#[derive(Default)]
+struct ShipOrder;
+
+#[async_trait]
+impl EventHandler for ShipOrder {
+    async fn handle(&self, dispatched: DispatchedEvent) {
+        println!("Shipping order: {}", dispatched.id());
+    }
+}
@@ -6,7 +6,7 @@ async fn main() {
     pretty_env_logger::init(); // For logging purpose only.
 
     // 1. Register event handler
-    UserCreated::subscribe::<HandleUserCreated>().await;
+    let _handle = UserCreated::subscribe::<HandleUserCreated>().await;
 
     // 2. Create event instance
     let event = UserCreated { id: 45 };
@@ -19,6 +19,12 @@ async fn main() {
         .listen_str::<HandleWorkflowEvent>(generic_event)
         // 3. Register a instance of a handler
         .listen_str_with(generic_event, HandleWorkflowEvent)
+        // 3.b Register a closure for every event whose name starts with "workflow/"
+        .listen_str_fn("workflow/**", |event| {
+            Box::pin(async move {
+                println!("handled by 'workflow/**' >> {:}", event.name());
+            })
+        })
         .build()
         .await;
 
@@ -30,6 +36,7 @@ async fn main() {
     // 5. Dispatch the event from the event instance
     WorkflowState::Started.dispatch_event_as(generic_event);
     WorkflowState::Completed.dispatch_event_as(generic_event);
+    WorkflowState::Stalled.dispatch_event_as("workflow/order/stalled");
 
     // The following line is use to pause the application for
     // few milliseconds. This will allow us to handle all dispatched events.
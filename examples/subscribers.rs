@@ -24,7 +24,7 @@ async fn main() {
     // 3. Another way to register subscribers
     //    A crate can use this method to register it's specific
     //    event handlers
-    Subscriber::new()
+    let group = Subscriber::named("welcome")
         .listen::<UserCreated, SendWelcomeEmail>()
         .listen::<UserCreated, HandleUserCreated>()
         .listen_with::<UserCreated>(HandleUserCreated) // Use an existing instance of your handler
@@ -34,6 +34,14 @@ async fn main() {
     let event = UserCreated { id: 1 };
     event.dispatch_event();
 
+    // 4. The handlers registered by a named subscriber can be managed as a group
+    sleep(Duration::from_millis(100)).await;
+    group.disable();
+
+    // Only the handlers registered in step 1 will handle this event
+    let event = UserCreated { id: 2 };
+    event.dispatch_event();
+
     // pause for a bit
     sleep(Duration::from_millis(100)).await;
 }
@@ -0,0 +1,33 @@
+use orsomafo::{Dispatchable, EventDispatcherBuilder, UnhandledPolicy};
+use tokio::time::{sleep, Duration};
+
+#[tokio::main]
+async fn main() {
+    pretty_env_logger::init(); // For logging purpose only.
+
+    let dispatcher = EventDispatcherBuilder::new()
+        // 1. Log a warning for events that nobody listens to.
+        //    Use `UnhandledPolicy::Reject` to refuse to dispatch them instead
+        .unhandled_policy(UnhandledPolicy::Warn)
+        // 2. Events that were dispatched but not handled end up here
+        .fallback_fn(|event| {
+            Box::pin(async move { println!("fallback handler received: {}", event.name()) })
+        })
+        .listen_str_fn("user.created", |_| Box::pin(async {}))
+        .build()
+        .await;
+
+    // 3. There is no subscriber for this event
+    dispatcher.dispatch_str("user.craeted", UserCreated { id: 1 });
+
+    // 4. The dispatcher counts the unhandled events per name
+    sleep(Duration::from_millis(100)).await;
+    println!("unhandled events: {:?}", dispatcher.unhandled_counts());
+}
+
+#[derive(Clone, serde::Deserialize, serde::Serialize)]
+struct UserCreated {
+    id: u32,
+}
+
+impl Dispatchable for UserCreated {}
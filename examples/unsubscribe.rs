@@ -7,10 +7,24 @@ async fn main() {
     pretty_env_logger::init(); // For logging purpose only.
 
     // 1. Subscribe to the `NumberAdded` event
-    NumberAdded::subscribe::<HandleNumberAddedEvent>().await;
+    let handle = NumberAdded::subscribe::<HandleNumberAddedEvent>().await;
+
+    // 1.b A closure can be unsubscribed the same way
+    let closure_handle = NumberAdded::subscribe_fn(|event| {
+        Box::pin(async move {
+            eprintln!(
+                "-----> You shouldn't see this message for event: {}",
+                event.name()
+            )
+        })
+    })
+    .await;
 
     // 2. Somewhere down the line, we unsubscribe
-    NumberAdded::unsubscribe::<HandleNumberAddedEvent>().await;
+    handle.unsubscribe();
+
+    // 2.b Dropping the handle also removes the registration
+    drop(closure_handle);
 
     // 3. An instance of the `NumberAdded` event is dispatched
     // This event will not be handled
@@ -0,0 +1,457 @@
+use crate::{
+    error::Error,
+    event_dispatcher::event_dispatcher,
+    event_listener::LOG_TITLE,
+    http::{self, Request, Response, ServerHandle},
+};
+use std::sync::Arc;
+use tokio::net::ToSocketAddrs;
+
+/// An HTTP API to operate the dispatcher
+///
+/// Every request must carry the token in an `authorization: Bearer <token>`
+/// header. In read-only mode only the `GET` routes are allowed.
+///
+/// | Route | Action |
+/// | ----- | ------ |
+/// | `GET /registry` | The registered handlers, see `EventDispatcher::registry` |
+/// | `GET /stats` | The statistics, see `EventDispatcher::stats` |
+/// | `GET /paused` | The paused event names with the number of held events |
+/// | `POST /paused/{name}` | Pauses the event name, see `EventDispatcher::pause` |
+/// | `DELETE /paused/{name}` | Resumes the event name, see `EventDispatcher::resume` |
+/// | `GET /dead-letters` | The dead letters, see `EventDispatcher::dead_letters` |
+/// | `GET /dead-letters/{id}` | A dead letter |
+/// | `POST /dead-letters/{id}/redrive` | Calls the handler that failed again, see `EventDispatcher::redrive` |
+/// | `DELETE /dead-letters/{id}` | Discards the dead letter |
+/// | `POST /events` | Dispatches a serialized event, see `EventDispatcher::try_dispatch_json` |
+///
+/// The event names in the paths are percent-encoded. The responses are JSON.
+/// An event that can not be read is answered with `400`, and one that the
+/// dispatcher refuses with `422`
+/// ```
+/// # use orsomafo::AdminServer;
+/// # #[tokio::main]
+/// # async fn main() {
+///    let server = AdminServer::new("secret")
+///        .read_only(true)
+///        .serve("127.0.0.1:0")
+///        .await
+///        .unwrap();
+///    println!("admin on http://{}", server.local_addr());
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct AdminServer {
+    token: String,
+    read_only: bool,
+}
+
+impl AdminServer {
+    /// An empty token refuses every request
+    pub fn new(token: impl Into<String>) -> Self {
+        Self {
+            token: token.into(),
+            read_only: false,
+        }
+    }
+
+    /// Only allows the routes that do not change the dispatcher. Defaults to false
+    pub fn read_only(mut self, read_only: bool) -> Self {
+        self.read_only = read_only;
+        self
+    }
+
+    /// Serves the API on the address
+    pub async fn serve(self, addr: impl ToSocketAddrs) -> std::io::Result<ServerHandle> {
+        let admin = Arc::new(self);
+        let checking = admin.clone();
+        http::serve(
+            addr,
+            move |request| checking.check(request),
+            move |request| {
+                let admin = admin.clone();
+                async move { admin.respond(&request).await }
+            },
+        )
+        .await
+    }
+
+    /// Refuses the request when it is not allowed, before its body is read
+    fn check(&self, request: &Request) -> Option<Response> {
+        if !self.is_authorized(request) {
+            return Some(error(401, "missing or invalid token"));
+        }
+        if self.read_only && request.method != "GET" {
+            return Some(error(403, "the admin API is read-only"));
+        }
+        None
+    }
+
+    async fn respond(&self, request: &Request) -> Response {
+        if let Some(response) = self.check(request) {
+            return response;
+        }
+
+        let path = request.path.split('?').next().unwrap_or_default();
+        let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+        let dispatcher = event_dispatcher();
+
+        match (request.method.as_str(), segments.as_slice()) {
+            ("GET", ["registry"]) => Response::json(200, &dispatcher.registry()),
+            ("GET", ["stats"]) => Response::json(200, &dispatcher.stats()),
+            ("GET", ["paused"]) => Response::json(200, &dispatcher.paused()),
+            ("POST", ["paused", name]) => {
+                let Some(name) = decode(name) else {
+                    return error(400, "invalid event name");
+                };
+                log::info!(target: LOG_TITLE, "admin: pausing event: {:?}", &name);
+                if dispatcher.pause(&name) {
+                    Response::json(200, &serde_json::json!({ "paused": name }))
+                } else {
+                    error(409, "the event is already paused")
+                }
+            }
+            ("DELETE", ["paused", name]) => {
+                let Some(name) = decode(name) else {
+                    return error(400, "invalid event name");
+                };
+                log::info!(target: LOG_TITLE, "admin: resuming event: {:?}", &name);
+                match dispatcher.resume(&name) {
+                    Some(released) => Response::json(
+                        200,
+                        &serde_json::json!({ "resumed": name, "released": released }),
+                    ),
+                    None => error(404, "the event is not paused"),
+                }
+            }
+            ("GET", ["dead-letters"]) => Response::json(200, &dispatcher.dead_letters()),
+            ("GET", ["dead-letters", id]) => match id.parse() {
+                Ok(id) => match dispatcher.dead_letter_by_id(id) {
+                    Some(letter) => Response::json(200, &letter),
+                    None => error(404, "the dead letter does not exist"),
+                },
+                Err(_) => error(400, "invalid dead letter ID"),
+            },
+            ("DELETE", ["dead-letters", id]) => match id.parse() {
+                Ok(id) => match dispatcher.discard_dead_letter(id) {
+                    Some(letter) => Response::json(200, &letter),
+                    None => error(404, "the dead letter does not exist"),
+                },
+                Err(_) => error(400, "invalid dead letter ID"),
+            },
+            ("POST", ["dead-letters", id, "redrive"]) => match id.parse() {
+                Ok(id) => {
+                    log::info!(target: LOG_TITLE, "admin: redriving dead letter: {}", id);
+                    match dispatcher.redrive(id).await {
+                        Ok(report) => Response::json(
+                            200,
+                            &serde_json::json!({
+                                "redriven": id,
+                                "handled_by": report.handled_by(),
+                                "failed": report.failed(),
+                            }),
+                        ),
+                        Err(e @ Error::DeadLetterNotFound { .. }) => error(404, &e.to_string()),
+                        Err(e) => error(409, &e.to_string()),
+                    }
+                }
+                Err(_) => error(400, "invalid dead letter ID"),
+            },
+            ("POST", ["events"]) => match dispatcher.try_dispatch_json(&request.body) {
+                Ok(id) => {
+                    log::info!(target: LOG_TITLE, "admin: dispatched event: {}", id);
+                    Response::json(202, &serde_json::json!({ "id": id }))
+                }
+                Err(e @ Error::InvalidEvent { .. }) => error(400, &e.to_string()),
+                Err(e) => error(422, &e.to_string()),
+            },
+            (
+                _,
+                ["registry" | "stats" | "paused" | "dead-letters" | "events"]
+                | ["paused" | "dead-letters", _]
+                | ["dead-letters", _, "redrive"],
+            ) => error(405, "method not allowed"),
+            _ => error(404, "not found"),
+        }
+    }
+
+    fn is_authorized(&self, request: &Request) -> bool {
+        let Some(token) = request
+            .header("authorization")
+            .and_then(|value| value.strip_prefix("Bearer "))
+        else {
+            return false;
+        };
+
+        // Compares every byte so that the time taken does not reveal the token
+        !self.token.is_empty()
+            && token.len() == self.token.len()
+            && token
+                .bytes()
+                .zip(self.token.bytes())
+                .fold(0, |diff, (a, b)| diff | (a ^ b))
+                == 0
+    }
+}
+
+fn error(status: u16, message: &str) -> Response {
+    Response::json(status, &serde_json::json!({ "error": message }))
+}
+
+/// Decodes a percent-encoded path segment
+fn decode(segment: &str) -> Option<String> {
+    let mut bytes = Vec::with_capacity(segment.len());
+    let mut input = segment.bytes();
+    while let Some(byte) = input.next() {
+        if byte == b'%' {
+            let hex = [input.next()?, input.next()?];
+            bytes.push(u8::from_str_radix(std::str::from_utf8(&hex).ok()?, 16).ok()?);
+        } else {
+            bytes.push(byte);
+        }
+    }
+
+    String::from_utf8(bytes)
+        .ok()
+        .filter(|name| !name.is_empty())
+}
+
+mod test {
+    #[tokio::test]
+    async fn test_admin_server() {
+        use super::*;
+        use crate::Dispatchable;
+        use std::{
+            sync::{
+                atomic::{AtomicUsize, Ordering},
+                Arc,
+            },
+            time::Duration,
+        };
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        #[derive(Clone, serde::Deserialize, serde::Serialize)]
+        struct AdminTested {
+            id: u32,
+        }
+
+        impl Dispatchable for AdminTested {}
+
+        async fn send(
+            server: &ServerHandle,
+            request: &str,
+            token: &str,
+            body: &str,
+        ) -> (u16, serde_json::Value) {
+            let (method, path) = request.split_once(' ').unwrap();
+            let mut stream = tokio::net::TcpStream::connect(server.local_addr())
+                .await
+                .unwrap();
+            let request = format!(
+                "{} {} HTTP/1.1\r\nhost: localhost\r\nauthorization: Bearer {}\r\ncontent-length: {}\r\n\r\n{}",
+                method,
+                path,
+                token,
+                body.len(),
+                body
+            );
+            stream.write_all(request.as_bytes()).await.unwrap();
+            let mut response = String::new();
+            stream.read_to_string(&mut response).await.unwrap();
+            let status = response[9..12].parse().unwrap();
+            let (_, body) = response.split_once("\r\n\r\n").unwrap();
+            (status, serde_json::from_str(body).unwrap())
+        }
+
+        let calls = Arc::new(AtomicUsize::new(0));
+        let counter = calls.clone();
+        let _handle = AdminTested::subscribe_fn(move |_| {
+            let call = counter.fetch_add(1, Ordering::SeqCst);
+            Box::pin(async move {
+                if call == 0 {
+                    panic!("the first call fails");
+                }
+            })
+        })
+        .await;
+
+        let server = AdminServer::new("secret")
+            .serve("127.0.0.1:0")
+            .await
+            .unwrap();
+        let name = AdminTested::event();
+        let encoded = name.replace(':', "%3A");
+
+        assert_eq!(send(&server, "GET /stats", "wrong", "").await.0, 401);
+        assert_eq!(
+            AdminServer::new("")
+                .respond(&get("/stats", ""))
+                .await
+                .status,
+            401
+        );
+        assert_eq!(send(&server, "GET /stats", "secret", "").await.0, 200);
+        assert_eq!(send(&server, "GET /nothing", "secret", "").await.0, 404);
+        assert_eq!(send(&server, "PUT /stats", "secret", "").await.0, 405);
+        assert_eq!(send(&server, "POST /events", "secret", "{").await.0, 400);
+        assert_eq!(
+            send(&server, "DELETE /dead-letters/0", "secret", "")
+                .await
+                .0,
+            404
+        );
+
+        // The first call fails and leaves a dead letter
+        let event = AdminTested { id: 1 }.serialize_event();
+        let (status, body) = send(&server, "POST /events", "secret", &event).await;
+        assert_eq!(status, 202);
+        assert!(body["id"].is_string());
+
+        let mut letter = None;
+        for _ in 0..100 {
+            let (_, letters) = send(&server, "GET /dead-letters", "secret", "").await;
+            letter = letters
+                .as_array()
+                .unwrap()
+                .iter()
+                .find(|letter| letter["event"]["name"] == name.as_str())
+                .cloned();
+            if letter.is_some() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        let letter = letter.expect("the failure was not recorded");
+        assert_eq!(letter["error"], "the first call fails");
+        let id = letter["id"].as_u64().unwrap();
+        let (status, body) =
+            send(&server, &format!("GET /dead-letters/{}", id), "secret", "").await;
+        assert_eq!((status, &body["id"]), (200, &letter["id"]));
+
+        // The event refused by the dispatcher is answered with 422
+        let mut cycle: serde_json::Value = serde_json::from_str(&event).unwrap();
+        cycle["chain"] = serde_json::json!([name]);
+        let (status, body) = send(&server, "POST /events", "secret", &cycle.to_string()).await;
+        assert_eq!(status, 422);
+        assert!(body["error"].as_str().unwrap().contains("cycle"));
+
+        // The dispatched events are held while the event is paused, and the
+        // dead letter can not be redriven
+        let paused = format!("/paused/{}", encoded);
+        assert_eq!(
+            send(&server, &format!("POST {}", paused), "secret", "")
+                .await
+                .0,
+            200
+        );
+        assert_eq!(
+            send(&server, &format!("POST {}", paused), "secret", "")
+                .await
+                .0,
+            409
+        );
+        let redrive = format!("POST /dead-letters/{}/redrive", id);
+        assert_eq!(send(&server, &redrive, "secret", "").await.0, 409);
+        assert_eq!(
+            send(&server, &format!("GET /dead-letters/{}", id), "secret", "")
+                .await
+                .0,
+            200
+        );
+        let held_event = AdminTested { id: 2 }.serialize_event();
+        assert_eq!(
+            send(&server, "POST /events", "secret", &held_event).await.0,
+            202
+        );
+
+        let mut held = serde_json::Value::Null;
+        for _ in 0..100 {
+            held = send(&server, "GET /paused", "secret", "").await.1[&name].clone();
+            if held == 1 {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        assert_eq!(held, 1);
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        let (status, body) = send(&server, &format!("DELETE {}", paused), "secret", "").await;
+        assert_eq!((status, &body["released"]), (200, &serde_json::json!(1)));
+        for _ in 0..100 {
+            if calls.load(Ordering::SeqCst) == 2 {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+        assert!(!event_dispatcher().is_paused(&name));
+
+        // The redrive calls the handler that failed
+        let (status, body) = send(&server, &redrive, "secret", "").await;
+        assert_eq!(status, 200);
+        assert_eq!(body["handled_by"].as_array().unwrap().len(), 1);
+        assert_eq!(body["failed"], serde_json::json!([]));
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+        assert_eq!(send(&server, &redrive, "secret", "").await.0, 404);
+
+        // The read-only mode only allows reading
+        let read_only = AdminServer::new("secret")
+            .read_only(true)
+            .serve("127.0.0.1:0")
+            .await
+            .unwrap();
+        let (status, registry) = send(&read_only, "GET /registry", "secret", "").await;
+        assert_eq!(status, 200);
+        assert!(registry["events"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .any(|event| event["name"] == name.as_str()));
+        assert_eq!(
+            send(&read_only, &format!("POST {}", paused), "secret", "")
+                .await
+                .0,
+            403
+        );
+        assert_eq!(
+            send(&read_only, "POST /events", "secret", &event).await.0,
+            403
+        );
+
+        // The body is limited, and only read once the token is checked
+        let head = |token: &str| {
+            format!(
+                "POST /events HTTP/1.1\r\nauthorization: Bearer {}\r\ncontent-length: 2000000\r\n\r\n",
+                token
+            )
+        };
+        for (token, status) in [
+            ("secret", "413 Payload Too Large"),
+            ("wrong", "401 Unauthorized"),
+        ] {
+            let mut stream = tokio::net::TcpStream::connect(server.local_addr())
+                .await
+                .unwrap();
+            stream.write_all(head(token).as_bytes()).await.unwrap();
+            let mut response = String::new();
+            stream.read_to_string(&mut response).await.unwrap();
+            assert!(response.starts_with(&format!("HTTP/1.1 {}\r\n", status)));
+        }
+
+        server.shutdown();
+        read_only.shutdown();
+
+        fn get(path: &str, token: &str) -> Request {
+            Request {
+                method: "GET".to_string(),
+                path: path.to_string(),
+                headers: vec![("authorization".to_string(), format!("Bearer {}", token))],
+                body: String::new(),
+            }
+        }
+
+        assert_eq!(decode("a%3A%3Ab/c"), Some("a::b/c".to_string()));
+        assert_eq!(decode("%zz"), None);
+        assert_eq!(decode(""), None);
+    }
+}
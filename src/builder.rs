@@ -1,27 +1,221 @@
 #![allow(dead_code)]
 use crate::{
+    causation::CausalHistory,
+    clock::{Clock, SystemClock},
     closure_handler_wrapper::ClosureHandlerWrapper,
+    dead_letter::DeadLetters,
     dispatched_event::DispatchedEvent,
+    error::Error,
     event::{Dispatchable, EventHandler},
-    event_dispatcher::{EventDispatcher, EVENT_DISPATCHER},
-    event_listener::{merge_subscribers, EventListener, Subscriber, SubscriberList, LOG_TITLE},
+    event_dispatcher::{DispatcherSettings, EventDispatcher, EVENT_DISPATCHER},
+    event_listener::{
+        merge_subscribers, EventListener, Queued, Registration, Subscriber, SubscriberList,
+        LOG_TITLE,
+    },
+    handler_id::HandlerId,
+    hooks::Hooks,
+    id_generator::{IdGenerator, UuidV7Generator},
+    middleware::Middleware,
+    namespace::{Namespace, NamespaceSettings},
+    pause::Paused,
+    topic::Topic,
+    unhandled::{Unhandled, UnhandledPolicy},
 };
 use futures::future::BoxFuture;
-use std::sync::Arc;
+use std::{sync::Arc, time::Duration};
 use tokio::sync::mpsc::{self};
 
-#[derive(Default)]
 pub struct EventDispatcherBuilder {
     subscribers: SubscriberList,
+    namespaces: Vec<(Topic, NamespaceSettings)>,
+    unhandled_policy: UnhandledPolicy,
+    fallback: Option<Box<dyn EventHandler>>,
+    causal_history: usize,
+    max_dispatch_depth: usize,
+    dead_letters: usize,
+    max_held_events: usize,
+    clock: Option<Arc<dyn Clock>>,
+    id_generator: Option<Arc<dyn IdGenerator>>,
+    middlewares: Vec<Arc<dyn Middleware>>,
+    hooks: Hooks,
+}
+
+const DEFAULT_CAUSAL_HISTORY: usize = 1024;
+const DEFAULT_MAX_DISPATCH_DEPTH: usize = 32;
+const DEFAULT_DEAD_LETTERS: usize = 1024;
+const DEFAULT_MAX_HELD_EVENTS: usize = 10_000;
+
+impl Default for EventDispatcherBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl EventDispatcherBuilder {
     pub fn new() -> Self {
         Self {
             subscribers: SubscriberList::new(),
+            namespaces: Vec::new(),
+            unhandled_policy: UnhandledPolicy::default(),
+            fallback: None,
+            causal_history: DEFAULT_CAUSAL_HISTORY,
+            max_dispatch_depth: DEFAULT_MAX_DISPATCH_DEPTH,
+            dead_letters: DEFAULT_DEAD_LETTERS,
+            max_held_events: DEFAULT_MAX_HELD_EVENTS,
+            clock: None,
+            id_generator: None,
+            middlewares: Vec::new(),
+            hooks: Hooks::default(),
         }
     }
 
+    /// Decides what happens to events without subscribers.
+    /// Unhandled events are counted regardless of the policy
+    ///
+    /// Only applied when the dispatcher is created by this builder
+    pub fn unhandled_policy(mut self, policy: UnhandledPolicy) -> Self {
+        self.unhandled_policy = policy;
+        self
+    }
+
+    /// Passes the events that were not handled by any handler to the fallback handler.
+    /// When the fallback handler panics or returns an error, the event is kept as a
+    /// dead letter whose handler has the registration ID `0`
+    ///
+    /// Only applied when the dispatcher is created by this builder
+    pub fn fallback_with(mut self, instance: impl EventHandler) -> Self {
+        self.fallback = Some(instance.to_handler());
+        self
+    }
+
+    /// Passes the events that were not handled by any handler to the closure. See `fallback_with`
+    pub fn fallback_fn(
+        self,
+        handler: impl Fn(DispatchedEvent) -> BoxFuture<'static, ()> + Send + Sync + 'static,
+    ) -> Self {
+        self.fallback_with(ClosureHandlerWrapper(handler))
+    }
+
+    /// Sets how many of the recently handled events are kept to answer
+    /// `EventDispatcher::causal_chain`. Defaults to 1024, `0` keeps none
+    ///
+    /// Only applied when the dispatcher is created by this builder
+    pub fn causal_history(mut self, capacity: usize) -> Self {
+        self.causal_history = capacity;
+        self
+    }
+
+    /// Sets how many events can lead to an event dispatched by a handler.
+    /// Deeper events are refused with `Error::MaxDepthExceeded`. Defaults to 32
+    ///
+    /// Only applied when the dispatcher is created by this builder
+    pub fn max_dispatch_depth(mut self, depth: usize) -> Self {
+        self.max_dispatch_depth = depth;
+        self
+    }
+
+    /// Sets how many of the events that a handler failed to handle are kept
+    /// to be inspected and redriven. Defaults to 1024, `0` keeps none.
+    /// See `EventDispatcher::dead_letters`
+    ///
+    /// Only applied when the dispatcher is created by this builder
+    pub fn dead_letters(mut self, capacity: usize) -> Self {
+        self.dead_letters = capacity;
+        self
+    }
+
+    /// Sets how many events are held per paused event name. The events
+    /// dispatched once the limit is reached are dropped. Defaults to 10000.
+    /// See `EventDispatcher::pause`
+    ///
+    /// Only applied when the dispatcher is created by this builder
+    pub fn max_held_events(mut self, max: usize) -> Self {
+        self.max_held_events = max;
+        self
+    }
+
+    /// Sets the clock the timestamps of the events are read from.
+    /// Defaults to the system clock
+    ///
+    /// Only applied when the dispatcher is created by this builder
+    pub fn clock(mut self, clock: impl Clock) -> Self {
+        self.clock = Some(Arc::new(clock));
+        self
+    }
+
+    /// Sets how the IDs of the events are created. Defaults to version 7 UUIDs.
+    /// See `UlidGenerator` and `SnowflakeGenerator`
+    ///
+    /// Only applied when the dispatcher is created by this builder
+    pub fn id_generator(mut self, generator: impl IdGenerator) -> Self {
+        self.id_generator = Some(Arc::new(generator));
+        self
+    }
+
+    /// Adds a middleware to the end of the chain. See `Middleware`
+    ///
+    /// Only applied when the dispatcher is created by this builder
+    pub fn middleware(mut self, middleware: impl Middleware) -> Self {
+        self.middlewares.push(Arc::new(middleware));
+        self
+    }
+
+    /// Calls the closure for every event that is about to be queued or,
+    /// with `dispatch_sync`, handled
+    ///
+    /// Hooks are only applied when the dispatcher is created by this builder.
+    /// A hook that panics is logged and the next hooks are still called
+    pub fn before_dispatch(
+        mut self,
+        hook: impl Fn(&DispatchedEvent) + Send + Sync + 'static,
+    ) -> Self {
+        self.hooks.before_dispatch.push(Arc::new(hook));
+        self
+    }
+
+    /// Calls the closure before a handler is called
+    pub fn before_handle(
+        mut self,
+        hook: impl Fn(&DispatchedEvent, &HandlerId) + Send + Sync + 'static,
+    ) -> Self {
+        self.hooks.before_handle.push(Arc::new(hook));
+        self
+    }
+
+    /// Calls the closure after a handler returned, with how long the handler took
+    pub fn after_handle(
+        mut self,
+        hook: impl Fn(&DispatchedEvent, &HandlerId, Duration) + Send + Sync + 'static,
+    ) -> Self {
+        self.hooks.after_handle.push(Arc::new(hook));
+        self
+    }
+
+    /// Calls the closure when a handler panicked or returned an error, with the message
+    pub fn on_error(
+        mut self,
+        hook: impl Fn(&DispatchedEvent, &HandlerId, &str) + Send + Sync + 'static,
+    ) -> Self {
+        self.hooks.on_error.push(Arc::new(hook));
+        self
+    }
+
+    /// Calls the closure when a handler is removed at the end of its lifetime.
+    /// See `Lifetime`
+    pub fn on_handler_removed(mut self, hook: impl Fn(&HandlerId) + Send + Sync + 'static) -> Self {
+        self.hooks.on_removed.push(Arc::new(hook));
+        self
+    }
+
+    /// Configures how the events dispatched under the topic are queued and handled.
+    /// Events are queued on the most specific namespace their topic is within
+    ///
+    /// Namespaces are only applied when the dispatcher is created by this builder
+    pub fn namespace(mut self, topic: &str, settings: NamespaceSettings) -> Self {
+        self.namespaces.push((Topic::parse(topic), settings));
+        self
+    }
+
     // TODO: complete implementation that will allow closure to be used as a handler
     pub fn listen_fn<E: Dispatchable>(
         self,
@@ -32,6 +226,7 @@ impl EventDispatcherBuilder {
         self.register(E::event(), wrapper.to_handler())
     }
 
+    /// Registers the closure for the event name or pattern. See `listen_str`
     pub fn listen_str_fn<F>(self, event: &str, handler: F) -> Self
     where
         F: Fn(DispatchedEvent) -> BoxFuture<'static, ()> + Send + Sync + 'static,
@@ -47,6 +242,35 @@ impl EventDispatcherBuilder {
         self.register(event, the_handler)
     }
 
+    /// Registers the handler for the event name or pattern
+    ///
+    /// Names are split into segments on `/`, like topics, and empty segments are
+    /// skipped. Dots do not separate segments: `user.*` is registered as an exact
+    /// name, with a warning. In a pattern, `*` matches exactly one segment and `**`
+    /// matches one or more segments. The pattern `*` on its own matches every event.
+    ///
+    /// Handlers registered for the exact name and the parent topics are called
+    /// before the handlers of matching patterns. Patterns are called from the
+    /// most specific (most literal segments, then fewest `**`) to the least
+    /// specific, with `*` last
+    /// ```
+    /// # use orsomafo::{DispatchedEvent, EventDispatcherBuilder, EventHandler};
+    /// # #[derive(Default)]
+    /// # struct AuditLog;
+    /// # #[orsomafo::async_trait]
+    /// # impl EventHandler for AuditLog {
+    /// #     async fn handle(&self, _event: DispatchedEvent) {}
+    /// # }
+    /// # #[tokio::main]
+    /// # async fn main() {
+    ///    EventDispatcherBuilder::new()
+    ///        .listen_str::<AuditLog>("user/*") // user/created, user/deleted
+    ///        .listen_str::<AuditLog>("orders/**") // orders/created, orders/eu/shipped
+    ///        .listen_str::<AuditLog>("*") // every event
+    ///        .build()
+    ///        .await;
+    /// # }
+    /// ```
     pub fn listen_str<H: EventHandler + Default>(self, event: &str) -> Self {
         let the_handler = H::default().to_handler();
         self.register(event.to_string(), the_handler)
@@ -58,6 +282,7 @@ impl EventDispatcherBuilder {
         self.register(event, the_handler)
     }
 
+    /// Registers the handler instance for the event name or pattern. See `listen_str`
     pub fn listen_str_with(self, event: &str, instance: impl EventHandler) -> Self {
         let the_handler = instance.to_handler();
         self.register(event.to_string(), the_handler)
@@ -81,47 +306,97 @@ impl EventDispatcherBuilder {
         self
     }
 
+    /// Creates the dispatcher, or adds the handlers to the running dispatcher
+    ///
+    /// # Panics
+    /// When the ordering constraints of the handlers form a cycle. See `try_build`
     pub async fn build(self) -> Arc<EventDispatcher> {
+        match self.try_build().await {
+            Ok(dispatcher) => dispatcher,
+            Err(error) => panic!("could not build the dispatcher: {}", error),
+        }
+    }
+
+    /// Creates the dispatcher, or adds the handlers to the running dispatcher
+    ///
+    /// Fails when the ordering constraints of the handlers form a cycle. None of
+    /// the handlers are registered and the dispatcher is not created in that case
+    pub async fn try_build(self) -> Result<Arc<EventDispatcher>, Error> {
         if let Some(dispatcher) = EVENT_DISPATCHER.get() {
-            let subscribers = self.subscribers;
-            merge_subscribers(subscribers).await;
-            dispatcher.clone()
+            if !self.namespaces.is_empty()
+                || self.fallback.is_some()
+                || self.unhandled_policy != UnhandledPolicy::default()
+                || self.causal_history != DEFAULT_CAUSAL_HISTORY
+                || self.max_dispatch_depth != DEFAULT_MAX_DISPATCH_DEPTH
+                || self.dead_letters != DEFAULT_DEAD_LETTERS
+                || self.max_held_events != DEFAULT_MAX_HELD_EVENTS
+                || self.clock.is_some()
+                || self.id_generator.is_some()
+                || !self.middlewares.is_empty()
+                || !self.hooks.is_empty()
+            {
+                log::warn!(
+                    target: LOG_TITLE,
+                    "the dispatcher is already running, namespaces, unhandled event, history, depth, dead letter, pause, clock, ID, middleware and hook settings are ignored"
+                );
+            }
+
+            merge_subscribers(self.subscribers)?;
+            Ok(dispatcher.clone())
         } else {
-            let (tx, rx) = mpsc::unbounded_channel::<DispatchedEvent>();
-            let subscribers = self.subscribers;
+            merge_subscribers(self.subscribers)?;
 
+            let (tx, rx) = mpsc::unbounded_channel::<Queued>();
             tokio::spawn(async move {
-                let mut handler = EventListener::new(subscribers, rx).await;
-                handler.receive().await;
+                EventListener::new(rx).receive().await;
             });
 
-            let dispatcher = Arc::new(EventDispatcher::new(tx));
+            let namespaces = self
+                .namespaces
+                .into_iter()
+                .map(|(topic, settings)| Namespace::start(topic, settings))
+                .collect();
+            let settings = DispatcherSettings {
+                unhandled: Unhandled::new(self.unhandled_policy, self.fallback.map(Arc::from)),
+                history: CausalHistory::new(self.causal_history),
+                max_depth: self.max_dispatch_depth,
+                dead_letters: DeadLetters::new(self.dead_letters),
+                paused: Paused::new(self.max_held_events),
+                clock: self.clock.unwrap_or_else(|| Arc::new(SystemClock)),
+                id_generator: self
+                    .id_generator
+                    .unwrap_or_else(|| Arc::new(UuidV7Generator)),
+                middlewares: self.middlewares,
+                hooks: self.hooks,
+            };
+            let dispatcher = Arc::new(EventDispatcher::new(tx, namespaces, settings));
 
             _ = EVENT_DISPATCHER.set(dispatcher.clone());
 
-            dispatcher
+            Ok(dispatcher)
         }
     }
 
     fn register(mut self, event: String, handler: Box<dyn EventHandler>) -> Self {
-        if self.subscribers.get(&event).is_none() {
+        if !self.subscribers.contains_key(&event) {
             self.subscribers.insert(event.clone(), Vec::new());
         }
 
+        let registration = Registration::new(handler);
         if let Some(collection) = self.subscribers.get_mut(&event) {
             log::trace!(
                 target: LOG_TITLE,
-                "registered handler: {:?}, for event: {:?}",
-                &event,
-                &handler.handler_id()
+                "registered handler: {}, for event: {:?}",
+                registration.handler_id(),
+                &event
             );
 
-            collection.push(handler);
+            collection.push(registration);
         } else {
             log::error!(
-                "could not register handler: {:?}, for event: {:?}",
-                &event,
-                &handler.handler_id()
+                "could not register handler: {}, for event: {:?}",
+                registration.handler_id(),
+                &event
             );
         }
 
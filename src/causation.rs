@@ -0,0 +1,170 @@
+use crate::{dispatched_event::DispatchedEvent, error::Error};
+use std::{
+    collections::VecDeque,
+    sync::{Mutex, PoisonError},
+};
+use uuid::Uuid;
+
+tokio::task_local! {
+    // The event being handled by the current task
+    static CURRENT_EVENT: Cause;
+}
+
+/// What an event dispatched by a handler inherits from the event being handled
+#[derive(Debug, Clone)]
+pub(crate) struct Cause {
+    pub(crate) id: Uuid,
+    pub(crate) correlation_id: Uuid,
+    /// The names of the events in the chain, ending with the event being handled
+    pub(crate) chain: Vec<String>,
+}
+
+impl Cause {
+    pub(crate) fn of(event: &DispatchedEvent) -> Self {
+        let mut chain = event.chain().to_vec();
+        chain.push(event.name());
+        Self {
+            id: event.id(),
+            correlation_id: event.correlation_id(),
+            chain,
+        }
+    }
+}
+
+/// Where an event sits in a chain of events that caused each other
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CausalLink {
+    id: Uuid,
+    name: String,
+    correlation_id: Uuid,
+    causation_id: Option<Uuid>,
+}
+
+impl CausalLink {
+    pub(crate) fn of(event: &DispatchedEvent) -> Self {
+        Self {
+            id: event.id(),
+            name: event.name(),
+            correlation_id: event.correlation_id(),
+            causation_id: event.causation_id(),
+        }
+    }
+
+    /// The ID of the event
+    pub fn id(&self) -> Uuid {
+        self.id
+    }
+
+    /// The name of the event
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The ID shared by all of the events in the chain
+    pub fn correlation_id(&self) -> Uuid {
+        self.correlation_id
+    }
+
+    /// The ID of the event whose handler dispatched this event
+    pub fn causation_id(&self) -> Option<Uuid> {
+        self.causation_id
+    }
+}
+
+/// Runs the future with the event set as the cause of the events it dispatches
+pub(crate) async fn with_cause<F: std::future::Future>(
+    event: &DispatchedEvent,
+    future: F,
+) -> F::Output {
+    CURRENT_EVENT.scope(Cause::of(event), future).await
+}
+
+/// The event being handled by the current task, if any
+pub(crate) fn current_cause() -> Option<Cause> {
+    CURRENT_EVENT.try_with(|cause| cause.clone()).ok()
+}
+
+/// Checks that dispatching the event does not repeat an event of its chain
+/// and that the chain is not deeper than the maximum
+pub(crate) fn check_chain(event: &DispatchedEvent, max_depth: usize) -> Result<(), Error> {
+    if let Some(start) = event
+        .chain()
+        .iter()
+        .position(|name| name == event.name_ref())
+    {
+        let mut chain = event.chain()[start..].to_vec();
+        chain.push(event.name());
+        return Err(Error::DispatchCycle {
+            event: event.name(),
+            chain,
+        });
+    }
+
+    if event.depth() > max_depth {
+        return Err(Error::MaxDepthExceeded {
+            event: event.name(),
+            max_depth,
+            chain: event.chain().to_vec(),
+        });
+    }
+
+    Ok(())
+}
+
+/// The most recently handled events
+#[derive(Debug)]
+pub(crate) struct CausalHistory {
+    capacity: usize,
+    links: Mutex<VecDeque<CausalLink>>,
+}
+
+impl CausalHistory {
+    pub(crate) fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            links: Mutex::new(VecDeque::new()),
+        }
+    }
+
+    pub(crate) fn record(&self, event: &DispatchedEvent) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        let mut links = self.links.lock().unwrap_or_else(PoisonError::into_inner);
+        if links.len() == self.capacity {
+            links.pop_front();
+        }
+        links.push_back(CausalLink::of(event));
+    }
+
+    /// The chain of events that led to the event, starting with the root event.
+    /// The chain stops at the first event that is no longer in the history
+    pub(crate) fn chain(&self, id: Uuid) -> Vec<CausalLink> {
+        let links = self.links.lock().unwrap_or_else(PoisonError::into_inner);
+        let mut chain = Vec::new();
+        let mut next = Some(id);
+        while let Some(id) = next {
+            match links.iter().rev().find(|link| link.id == id) {
+                Some(link) if !chain.contains(link) => {
+                    next = link.causation_id;
+                    chain.push(link.clone());
+                }
+                _ => break,
+            }
+        }
+        chain.reverse();
+        chain
+    }
+
+    /// All of the events in the history with the correlation ID, oldest first
+    pub(crate) fn correlated(&self, correlation_id: Uuid) -> Vec<CausalLink> {
+        self.links
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .iter()
+            .filter(|link| link.correlation_id == correlation_id)
+            .cloned()
+            .collect()
+    }
+}
@@ -0,0 +1,81 @@
+use crate::event_dispatcher::EVENT_DISPATCHER;
+use chrono::{DateTime, Utc};
+use std::sync::{Mutex, PoisonError};
+
+/// The source of the timestamps of the events
+///
+/// Set with `EventDispatcherBuilder::clock`. Use a `ManualClock` to freeze or
+/// advance the time in tests
+pub trait Clock: Send + Sync + 'static {
+    /// The current time
+    fn now(&self) -> DateTime<Utc>;
+}
+
+/// Reads the time from the system. This is the default clock
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+}
+
+/// A clock that only moves when it is told to
+/// ```
+/// # use orsomafo::{Clock, ManualClock};
+/// # use chrono::{Duration, TimeZone, Utc};
+///    let clock = ManualClock::new(Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap());
+///    clock.advance(Duration::milliseconds(5));
+///
+///    assert_eq!(clock.now().timestamp_millis() % 1000, 5);
+/// ```
+#[derive(Debug)]
+pub struct ManualClock {
+    now: Mutex<DateTime<Utc>>,
+}
+
+impl ManualClock {
+    /// Creates a clock frozen at the given time
+    pub fn new(now: DateTime<Utc>) -> Self {
+        Self {
+            now: Mutex::new(now),
+        }
+    }
+
+    /// Moves the clock to the given time
+    pub fn set(&self, now: DateTime<Utc>) {
+        *self.now.lock().unwrap_or_else(PoisonError::into_inner) = now;
+    }
+
+    /// Moves the clock forward
+    pub fn advance(&self, duration: chrono::Duration) {
+        *self.now.lock().unwrap_or_else(PoisonError::into_inner) += duration;
+    }
+}
+
+impl Default for ManualClock {
+    fn default() -> Self {
+        Self::new(Utc::now())
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> DateTime<Utc> {
+        *self.now.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl<C: Clock> Clock for std::sync::Arc<C> {
+    fn now(&self) -> DateTime<Utc> {
+        self.as_ref().now()
+    }
+}
+
+/// The current time according to the clock of the dispatcher
+pub(crate) fn now() -> DateTime<Utc> {
+    match EVENT_DISPATCHER.get() {
+        Some(dispatcher) => dispatcher.clock().now(),
+        None => Utc::now(),
+    }
+}
@@ -16,3 +16,25 @@ where
         (self.0)(event).await;
     }
 }
+
+/// Turns a closure into a handler
+///
+/// Useful when the closure needs to be configured before it is registered
+/// ```
+/// # use orsomafo::{handler_fn, Dispatchable, DispatchedEvent, EventHandler};
+/// # #[tokio::main]
+/// # async fn main() {
+///    #[derive(Clone, serde::Serialize, serde::Deserialize)]
+///    struct MyEvent;
+///    impl Dispatchable for MyEvent {}
+///
+///    let handler = handler_fn(|event: DispatchedEvent| Box::pin(async move { println!("{}", event.name()) }));
+///    let _handle = MyEvent::subscribe_with(handler.with_label("printer")).await;
+/// # }
+/// ```
+pub fn handler_fn<F>(handler: F) -> impl EventHandler
+where
+    F: Fn(DispatchedEvent) -> BoxFuture<'static, ()> + Send + Sync + 'static,
+{
+    ClosureHandlerWrapper(handler)
+}
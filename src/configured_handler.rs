@@ -0,0 +1,140 @@
+use crate::{DispatchedEvent, EventHandler, Filter, Lifetime, Outcome};
+use async_trait::async_trait;
+
+/// Wraps a handler and overrides how it is registered
+///
+/// Created by calling `with_label`, `with_priority`, `run_before`, `run_after`,
+/// `with_lifetime` or `with_filter` on any handler
+/// ```
+/// # use orsomafo::{Dispatchable, DispatchedEvent, EventHandler};
+/// # #[tokio::main]
+/// # async fn main() {
+///    #[derive(Clone, serde::Serialize, serde::Deserialize)]
+///    struct MyEvent;
+///    impl Dispatchable for MyEvent {}
+///
+///    struct SendEmail(&'static str);
+///
+///    #[orsomafo::async_trait]
+///    impl EventHandler for SendEmail {
+///        async fn handle(&self, _event: DispatchedEvent) {}
+///    }
+///
+///    let handle = MyEvent::subscribe_with(SendEmail("admin@example.com").with_label("admin")).await;
+///    assert_eq!(handle.handler_id().label(), Some("admin"));
+/// # }
+/// ```
+pub struct ConfiguredHandler<H: EventHandler> {
+    handler: H,
+    label: Option<String>,
+    priority: i32,
+    run_before: Vec<String>,
+    run_after: Vec<String>,
+    lifetime: Lifetime,
+    filter: Option<Filter>,
+}
+
+impl<H: EventHandler> ConfiguredHandler<H> {
+    pub(crate) fn new(handler: H) -> Self {
+        Self {
+            label: handler.label(),
+            priority: handler.priority(),
+            run_before: handler.runs_before(),
+            run_after: handler.runs_after(),
+            lifetime: handler.lifetime(),
+            filter: handler.filter(),
+            handler,
+        }
+    }
+
+    /// Sets the label of the registration
+    pub fn with_label(mut self, label: &str) -> Self {
+        self.label = Some(label.to_string());
+        self
+    }
+
+    /// Sets the priority of the registration
+    pub fn with_priority(mut self, priority: i32) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    /// Calls this handler before the handler with the given label or name
+    pub fn run_before(mut self, name: &str) -> Self {
+        self.run_before.push(name.to_string());
+        self
+    }
+
+    /// Calls this handler after the handler with the given label or name
+    pub fn run_after(mut self, name: &str) -> Self {
+        self.run_after.push(name.to_string());
+        self
+    }
+
+    /// Sets how long the registration stays registered
+    pub fn with_lifetime(mut self, lifetime: Lifetime) -> Self {
+        self.lifetime = lifetime;
+        self
+    }
+
+    /// Only passes the events accepted by the filter to the handler.
+    /// When called more than once, an event must be accepted by all of the filters
+    pub fn with_filter(mut self, filter: Filter) -> Self {
+        self.filter = Some(match self.filter.take() {
+            Some(existing) => existing.and(filter),
+            None => filter,
+        });
+        self
+    }
+}
+
+#[async_trait]
+impl<H: EventHandler> EventHandler for ConfiguredHandler<H> {
+    async fn handle(&self, event: DispatchedEvent) {
+        self.handler.handle(event).await;
+    }
+
+    async fn handle_event(&self, event: DispatchedEvent) -> Outcome {
+        self.handler.handle_event(event).await
+    }
+
+    async fn try_handle_event(&self, event: DispatchedEvent) -> Result<Outcome, String> {
+        self.handler.try_handle_event(event).await
+    }
+
+    fn handler_id(&self) -> String {
+        self.handler.handler_id()
+    }
+
+    fn label(&self) -> Option<String> {
+        self.label.clone()
+    }
+
+    fn priority(&self) -> i32 {
+        self.priority
+    }
+
+    fn runs_before(&self) -> Vec<String> {
+        self.run_before.clone()
+    }
+
+    fn runs_after(&self) -> Vec<String> {
+        self.run_after.clone()
+    }
+
+    fn execute_once(&self) -> bool {
+        self.handler.execute_once()
+    }
+
+    fn lifetime(&self) -> Lifetime {
+        self.lifetime.clone()
+    }
+
+    fn filter(&self) -> Option<Filter> {
+        self.filter.clone()
+    }
+
+    fn propagate(&self) -> bool {
+        self.handler.propagate()
+    }
+}
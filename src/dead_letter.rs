@@ -0,0 +1,162 @@
+use crate::{dispatched_event::DispatchedEvent, handler_id::HandlerId};
+use chrono::{DateTime, Utc};
+use std::{
+    collections::VecDeque,
+    sync::{Mutex, PoisonError},
+};
+
+/// An event that a handler failed to handle
+///
+/// See `EventDispatcher::dead_letters` and `EventDispatcher::redrive`
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct DeadLetter {
+    id: u64,
+    handler: HandlerId,
+    error: String,
+    failed_at: DateTime<Utc>,
+    event: DispatchedEvent,
+}
+
+impl DeadLetter {
+    /// The ID of the dead letter, unique within the dispatcher
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    /// The handler that failed
+    pub fn handler(&self) -> &HandlerId {
+        &self.handler
+    }
+
+    /// The panic message or the error returned by the handler
+    pub fn error(&self) -> &str {
+        &self.error
+    }
+
+    pub fn failed_at(&self) -> DateTime<Utc> {
+        self.failed_at
+    }
+
+    pub fn event(&self) -> &DispatchedEvent {
+        &self.event
+    }
+}
+
+/// The most recent dead letters
+#[derive(Debug)]
+pub(crate) struct DeadLetters {
+    capacity: usize,
+    letters: Mutex<(u64, VecDeque<DeadLetter>)>,
+}
+
+impl DeadLetters {
+    pub(crate) fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            letters: Mutex::new((1, VecDeque::new())),
+        }
+    }
+
+    pub(crate) fn push(
+        &self,
+        event: DispatchedEvent,
+        handler: HandlerId,
+        error: String,
+        failed_at: DateTime<Utc>,
+    ) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        let mut letters = self.letters.lock().unwrap_or_else(PoisonError::into_inner);
+        let (next_id, letters) = &mut *letters;
+        if letters.len() == self.capacity {
+            letters.pop_front();
+        }
+        letters.push_back(DeadLetter {
+            id: *next_id,
+            handler,
+            error,
+            failed_at,
+            event,
+        });
+        *next_id += 1;
+    }
+
+    /// Puts back a dead letter that was taken
+    pub(crate) fn restore(&self, letter: DeadLetter) {
+        let mut letters = self.letters.lock().unwrap_or_else(PoisonError::into_inner);
+        let letters = &mut letters.1;
+        let position = letters
+            .iter()
+            .position(|l| l.id > letter.id)
+            .unwrap_or(letters.len());
+        letters.insert(position, letter);
+    }
+
+    /// All of the dead letters, oldest first
+    pub(crate) fn all(&self) -> Vec<DeadLetter> {
+        self.letters
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .1
+            .iter()
+            .cloned()
+            .collect()
+    }
+
+    pub(crate) fn get(&self, id: u64) -> Option<DeadLetter> {
+        self.letters
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .1
+            .iter()
+            .find(|letter| letter.id == id)
+            .cloned()
+    }
+
+    pub(crate) fn take(&self, id: u64) -> Option<DeadLetter> {
+        let mut letters = self.letters.lock().unwrap_or_else(PoisonError::into_inner);
+        let letters = &mut letters.1;
+        let position = letters.iter().position(|letter| letter.id == id)?;
+        letters.remove(position)
+    }
+}
+
+mod test {
+    #[test]
+    fn test_dead_letters() {
+        use super::*;
+
+        let letters = DeadLetters::new(2);
+        let handler = HandlerId::new(1, "audit".to_string(), None);
+        for name in ["a", "b", "c"] {
+            letters.push(
+                DispatchedEvent::new("{}".to_string(), name.to_string()),
+                handler.clone(),
+                "boom".to_string(),
+                Utc::now(),
+            );
+        }
+
+        let all = letters.all();
+        assert_eq!(all.len(), 2);
+        assert_eq!(all[0].id(), 2);
+        assert_eq!(all[1].event().name(), "c");
+        assert_eq!(letters.get(3).unwrap().error(), "boom");
+
+        let taken = letters.take(2).unwrap();
+        assert!(letters.get(2).is_none());
+        letters.restore(taken);
+        assert_eq!(letters.all()[0].id(), 2);
+
+        let disabled = DeadLetters::new(0);
+        disabled.push(
+            DispatchedEvent::new("{}".to_string(), "a".to_string()),
+            handler,
+            "boom".to_string(),
+            Utc::now(),
+        );
+        assert!(disabled.all().is_empty());
+    }
+}
@@ -0,0 +1,106 @@
+use crate::{
+    dispatch_report::DispatchReport, dispatched_event::DispatchedEvent, error::Error,
+    event::Dispatchable, event_dispatcher::EventDispatcher,
+};
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+/// Prepares an event before it is dispatched
+///
+/// Created by `EventDispatcher::event`
+/// ```
+/// # use orsomafo::{event_dispatcher, Dispatchable, DispatchedEvent};
+/// # #[tokio::main]
+/// # async fn main() {
+///    #[derive(Clone, serde::Serialize, serde::Deserialize)]
+///    struct InvoicePaid;
+///    impl Dispatchable for InvoicePaid {}
+///
+///    event_dispatcher()
+///        .event(InvoicePaid)
+///        .header("tenant", "acme")
+///        .header("schema_version", 2)
+///        .dispatch();
+/// # }
+/// ```
+pub struct DispatchBuilder<'a> {
+    dispatcher: &'a EventDispatcher,
+    event: DispatchedEvent,
+}
+
+impl<'a> DispatchBuilder<'a> {
+    pub(crate) fn new<T: Dispatchable>(dispatcher: &'a EventDispatcher, event: T) -> Self {
+        Self {
+            dispatcher,
+            event: DispatchedEvent::new(
+                serde_json::to_string(&event).expect("could not serialize event"),
+                T::event(),
+            ),
+        }
+    }
+
+    /// Uses the given ID instead of generating one. Use it to reuse the ID of
+    /// an upstream message, so that the event can be recognized when it is
+    /// dispatched again
+    pub fn id(mut self, id: Uuid) -> Self {
+        self.event.set_id(id);
+        self
+    }
+
+    /// Dispatches the event under the given name instead of the event's name
+    pub fn name(mut self, name: &str) -> Self {
+        self.event.set_name(name.to_string());
+        self
+    }
+
+    /// Attaches metadata to the event
+    pub fn header(mut self, key: &str, value: impl Into<serde_json::Value>) -> Self {
+        self.event.set_header(key.to_string(), value.into());
+        self
+    }
+
+    /// Sets when the event occurred, when it is dispatched later than that
+    pub fn occurred_at(mut self, occurred_at: DateTime<Utc>) -> Self {
+        self.event.set_occurred_at(occurred_at);
+        self
+    }
+
+    /// Makes the event part of the chain of the given event.
+    /// Use it when dispatching from a task spawned by a handler, where the
+    /// cause is not picked up automatically
+    pub fn caused_by(mut self, cause: &DispatchedEvent) -> Self {
+        self.event.set_cause(cause);
+        self
+    }
+
+    /// Sets the correlation ID of the event, for example to continue
+    /// a chain started outside of this process
+    pub fn correlation_id(mut self, correlation_id: Uuid) -> Self {
+        self.event.set_correlation_id(correlation_id);
+        self
+    }
+
+    /// Dispatches the event
+    pub fn dispatch(self) {
+        _ = self.try_dispatch();
+    }
+
+    /// Dispatches the event.
+    /// Fails when the event has no subscribers and `UnhandledPolicy::Reject` is used
+    pub fn try_dispatch(self) -> Result<(), Error> {
+        self.dispatcher.send(self.event)
+    }
+
+    /// Dispatches the event in the current thread and reports how it was handled.
+    /// The report is empty when the event could not be dispatched
+    pub async fn dispatch_and_wait(self) -> DispatchReport {
+        let report = DispatchReport::new(self.event.id(), self.event.name());
+        self.try_dispatch_and_wait().await.unwrap_or(report)
+    }
+
+    /// Dispatches the event in the current thread and reports how it was handled.
+    /// Fails when the event would be dispatched in a cycle or deeper than allowed
+    pub async fn try_dispatch_and_wait(self) -> Result<DispatchReport, Error> {
+        self.dispatcher.call(self.event).await
+    }
+}
@@ -0,0 +1,87 @@
+use crate::handler_id::HandlerId;
+use uuid::Uuid;
+
+/// What happened while an event was being handled
+///
+/// Returned by `EventDispatcher::dispatch_and_wait`
+#[derive(Debug, Clone)]
+pub struct DispatchReport {
+    event_id: Uuid,
+    name: String,
+    handled_by: Vec<HandlerId>,
+    stopped_by: Option<HandlerId>,
+    stopped_before: Option<HandlerId>,
+    failed: Vec<HandlerId>,
+    skipped: Vec<HandlerId>,
+}
+
+impl DispatchReport {
+    pub(crate) fn new(event_id: Uuid, name: String) -> Self {
+        Self {
+            event_id,
+            name,
+            handled_by: Vec::new(),
+            stopped_by: None,
+            stopped_before: None,
+            failed: Vec::new(),
+            skipped: Vec::new(),
+        }
+    }
+
+    /// The ID of the dispatched event
+    pub fn event_id(&self) -> Uuid {
+        self.event_id
+    }
+
+    /// The name of the dispatched event
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The handlers that handled the event, in the order they were called.
+    /// The handlers that failed are only listed in `failed`
+    pub fn handled_by(&self) -> &[HandlerId] {
+        &self.handled_by
+    }
+
+    /// The handler that stopped the event from propagating to the remaining handlers
+    pub fn stopped_by(&self) -> Option<&HandlerId> {
+        self.stopped_by.as_ref()
+    }
+
+    /// The handler whose middlewares stopped the event from propagating to the
+    /// remaining handlers, without calling it. The handler is also listed in `skipped`
+    pub fn stopped_before(&self) -> Option<&HandlerId> {
+        self.stopped_before.as_ref()
+    }
+
+    /// The handlers the middlewares did not let the event reach, in order
+    pub fn skipped(&self) -> &[HandlerId] {
+        &self.skipped
+    }
+
+    /// The handlers that panicked or returned an error while handling the event
+    pub fn failed(&self) -> &[HandlerId] {
+        &self.failed
+    }
+
+    pub(crate) fn record(&mut self, handler: HandlerId) {
+        self.handled_by.push(handler);
+    }
+
+    pub(crate) fn fail(&mut self, handler: HandlerId) {
+        self.failed.push(handler);
+    }
+
+    pub(crate) fn stop(&mut self, handler: HandlerId) {
+        self.stopped_by = Some(handler);
+    }
+
+    pub(crate) fn skip(&mut self, handler: HandlerId) {
+        self.skipped.push(handler);
+    }
+
+    pub(crate) fn stop_before(&mut self, handler: HandlerId) {
+        self.stopped_before = Some(handler);
+    }
+}
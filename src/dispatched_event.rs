@@ -1,46 +1,140 @@
 #![allow(dead_code)]
 use chrono::{DateTime, TimeZone, Utc};
+use std::collections::BTreeMap;
 use uuid::Uuid;
 
-use crate::Dispatchable;
+use crate::{
+    causation::{current_cause, Cause},
+    clock, id_generator,
+    topic::Topic,
+    Dispatchable,
+};
 
 #[derive(Debug, Clone, serde::Deserialize, serde::Serialize)]
 pub struct DispatchedEvent {
     id: Uuid,
+    /// Seconds since the epoch, kept for the consumers of older versions
     created_at: i64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    occurred_at: Option<DateTime<Utc>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    dispatched_at: Option<DateTime<Utc>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    handled_at: Option<DateTime<Utc>>,
     data: String,
     name: String,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    headers: BTreeMap<String, serde_json::Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    correlation_id: Option<Uuid>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    causation_id: Option<Uuid>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    chain: Vec<String>,
+    #[serde(default)]
+    sequence: u64,
 }
 
 impl DispatchedEvent {
+    /// Events created while an event is being handled are caused by that event
     pub(crate) fn new(data: String, name: String) -> Self {
+        let mut cause = current_cause();
+        let now = clock::now();
         Self {
-            id: Uuid::now_v7(),
-            created_at: chrono::Utc::now().timestamp(),
+            id: id_generator::next_id(),
+            created_at: now.timestamp(),
+            occurred_at: Some(now),
+            dispatched_at: None,
+            handled_at: None,
             data,
             name,
+            headers: BTreeMap::new(),
+            correlation_id: cause.as_ref().map(|cause| cause.correlation_id),
+            causation_id: cause.as_ref().map(|cause| cause.id),
+            chain: cause.take().map(|cause| cause.chain).unwrap_or_default(),
+            sequence: 0,
         }
     }
 
+    /// Makes the event part of the chain of the given event
+    pub(crate) fn set_cause(&mut self, cause: &DispatchedEvent) {
+        let cause = Cause::of(cause);
+        self.correlation_id = Some(cause.correlation_id);
+        self.causation_id = Some(cause.id);
+        self.chain = cause.chain;
+    }
+
+    pub(crate) fn set_correlation_id(&mut self, correlation_id: Uuid) {
+        self.correlation_id = Some(correlation_id);
+    }
+
+    pub(crate) fn set_id(&mut self, id: Uuid) {
+        self.id = id;
+    }
+
+    pub(crate) fn set_sequence(&mut self, sequence: u64) {
+        self.sequence = sequence;
+    }
+
+    pub(crate) fn set_name(&mut self, name: String) {
+        self.name = name;
+    }
+
+    pub(crate) fn set_occurred_at(&mut self, occurred_at: DateTime<Utc>) {
+        self.created_at = occurred_at.timestamp();
+        self.occurred_at = Some(occurred_at);
+    }
+
+    pub(crate) fn set_dispatched_at(&mut self, dispatched_at: DateTime<Utc>) {
+        self.dispatched_at = Some(dispatched_at);
+    }
+
+    pub(crate) fn set_handled_at(&mut self, handled_at: DateTime<Utc>) {
+        self.handled_at = Some(handled_at);
+    }
+
+    pub(crate) fn set_header(&mut self, key: String, value: serde_json::Value) {
+        self.headers.insert(key, value);
+    }
+
     pub fn id(&self) -> Uuid {
-        self.id.clone()
+        self.id
     }
 
     pub fn id_ref(&self) -> &Uuid {
         &self.id
     }
 
+    /// When the event occurred. Same as `occurred_at`
     pub fn created_at(&self) -> DateTime<Utc> {
-        Utc.timestamp_opt(self.created_at, 0)
-            .single()
-            .expect("could not parse event timestamp")
+        self.occurred_at()
     }
 
-    /// Returns the timestamp
+    /// Returns the timestamp in seconds
     pub fn created_at_ts(&self) -> i64 {
         self.created_at
     }
 
+    /// When the event occurred, with full precision. Events serialized by older
+    /// versions only have a precision of a second
+    pub fn occurred_at(&self) -> DateTime<Utc> {
+        self.occurred_at.unwrap_or_else(|| {
+            Utc.timestamp_opt(self.created_at, 0)
+                .single()
+                .expect("could not parse event timestamp")
+        })
+    }
+
+    /// When the event was handed to the dispatcher
+    pub fn dispatched_at(&self) -> Option<DateTime<Utc>> {
+        self.dispatched_at
+    }
+
+    /// When the dispatcher started calling the handlers of the event
+    pub fn handled_at(&self) -> Option<DateTime<Utc>> {
+        self.handled_at
+    }
+
     pub fn name(&self) -> String {
         self.name.clone()
     }
@@ -49,6 +143,11 @@ impl DispatchedEvent {
         &self.name
     }
 
+    /// The event name parsed as a topic. See `Topic`
+    pub fn topic(&self) -> Topic {
+        Topic::parse(&self.name)
+    }
+
     pub fn data(&self) -> String {
         self.data.clone()
     }
@@ -57,6 +156,69 @@ impl DispatchedEvent {
         &self.data
     }
 
+    /// The metadata attached to the event when it was dispatched
+    pub fn headers(&self) -> &BTreeMap<String, serde_json::Value> {
+        &self.headers
+    }
+
+    /// Returns the value of the header
+    pub fn header(&self, key: &str) -> Option<&serde_json::Value> {
+        self.headers.get(key)
+    }
+
+    /// Returns the value of the header when it is a string
+    pub fn header_str(&self, key: &str) -> Option<&str> {
+        self.headers.get(key).and_then(|value| value.as_str())
+    }
+
+    /// The ID shared by every event in the chain started by the same root event.
+    /// A root event is correlated with itself
+    pub fn correlation_id(&self) -> Uuid {
+        self.correlation_id.unwrap_or(self.id)
+    }
+
+    /// The ID of the event whose handler dispatched this event.
+    /// `None` for root events
+    pub fn causation_id(&self) -> Option<Uuid> {
+        self.causation_id
+    }
+
+    /// The position of the event among the events dispatched by the dispatcher,
+    /// starting at 1. `0` when the event has not been dispatched. See `SequenceTracker`
+    pub fn sequence(&self) -> u64 {
+        self.sequence
+    }
+
+    /// The names of the events that led to this event, starting with the root event
+    pub fn chain(&self) -> &[String] {
+        &self.chain
+    }
+
+    /// How many events led to this event. Root events have a depth of 0
+    pub fn depth(&self) -> usize {
+        self.chain.len()
+    }
+
+    /// Attaches metadata to the event, replacing the value of an existing header
+    pub fn insert_header(&mut self, key: &str, value: impl Into<serde_json::Value>) {
+        self.headers.insert(key.to_string(), value.into());
+    }
+
+    /// Removes the header from the event
+    pub fn remove_header(&mut self, key: &str) -> Option<serde_json::Value> {
+        self.headers.remove(key)
+    }
+
+    /// Changes the name the event is dispatched under
+    pub fn rename(&mut self, name: &str) {
+        self.name = name.to_string();
+    }
+
+    /// Replaces the data of the event
+    pub fn replace_event<T: Dispatchable>(&mut self, event: &T) {
+        self.data = serde_json::to_string(event).expect("could not serialize event");
+    }
+
     /// Returns the actual instance of the event
     /// ```
     /// # use async_trait::async_trait;
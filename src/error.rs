@@ -0,0 +1,89 @@
+use crate::handler_id::HandlerId;
+use std::fmt::Display;
+
+/// Errors returned by the dispatcher
+///
+/// New variants may be added in minor releases, so matches need a wildcard arm
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Error {
+    /// The `run_before`/`run_after` constraints of the handlers registered
+    /// for the event form a cycle. The registration was rejected
+    OrderingCycle {
+        event: String,
+        handlers: Vec<HandlerId>,
+    },
+    /// The event has no subscribers and the dispatcher is configured to
+    /// reject such events. See `UnhandledPolicy::Reject`
+    NoSubscribers { event: String },
+    /// The event was dispatched by a handler of an event that it caused.
+    /// The chain lists the event names from the first occurrence of the event
+    DispatchCycle { event: String, chain: Vec<String> },
+    /// The event was dispatched deeper in a chain of events than allowed.
+    /// See `EventDispatcherBuilder::max_dispatch_depth`
+    MaxDepthExceeded {
+        event: String,
+        max_depth: usize,
+        chain: Vec<String>,
+    },
+    /// A middleware refused to dispatch the event. See `Middleware::on_dispatch`
+    Rejected { event: String, reason: String },
+    /// The serialized event could not be read. See `EventDispatcher::try_dispatch_json`
+    InvalidEvent { reason: String },
+    /// There is no dead letter with the ID. See `EventDispatcher::redrive`
+    DeadLetterNotFound { id: u64 },
+    /// The handler is no longer registered for the event, or is disabled.
+    /// See `EventDispatcher::redrive`
+    HandlerNotFound { event: String, handler: HandlerId },
+    /// The event name is paused, so the event can not be handled in the
+    /// current thread. See `EventDispatcher::pause`
+    Paused { event: String },
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::OrderingCycle { event, handlers } => write!(
+                f,
+                "the ordering constraints for event {:?} form a cycle between: {}",
+                event,
+                handlers
+                    .iter()
+                    .map(|h| h.to_string())
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            ),
+            Self::NoSubscribers { event } => write!(f, "event {:?} has no subscribers", event),
+            Self::DispatchCycle { event, chain } => write!(
+                f,
+                "event {:?} was dispatched in a cycle: {}",
+                event,
+                chain.join(" -> ")
+            ),
+            Self::MaxDepthExceeded {
+                event,
+                max_depth,
+                chain,
+            } => write!(
+                f,
+                "event {:?} exceeds the maximum dispatch depth of {}: {}",
+                event,
+                max_depth,
+                chain.join(" -> ")
+            ),
+            Self::Rejected { event, reason } => {
+                write!(f, "event {:?} was rejected: {}", event, reason)
+            }
+            Self::InvalidEvent { reason } => write!(f, "the event could not be read: {}", reason),
+            Self::DeadLetterNotFound { id } => write!(f, "dead letter {} does not exist", id),
+            Self::HandlerNotFound { event, handler } => write!(
+                f,
+                "handler {} is not registered for event {:?}",
+                handler, event
+            ),
+            Self::Paused { event } => write!(f, "event {:?} is paused", event),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
@@ -1,8 +1,18 @@
 use crate::{
     closure_handler_wrapper::ClosureHandlerWrapper,
+    configured_handler::ConfiguredHandler,
     dispatched_event::DispatchedEvent,
+    error::Error,
     event_dispatcher::event_dispatcher,
-    event_listener::{merge_subscribers, unsubscribe, SubscriberList, LOG_TITLE},
+    event_listener::{
+        merge_subscribers, unsubscribe, unsubscribe_label, unsubscribe_registration, Registration,
+        SubscriberList, LOG_TITLE,
+    },
+    filter::Filter,
+    handler_id::HandlerId,
+    lifetime::Lifetime,
+    propagation::inject_context,
+    subscription_handle::SubscriptionHandle,
 };
 use async_trait::async_trait;
 use futures::future::BoxFuture;
@@ -57,70 +67,104 @@ pub trait Dispatchable:
     }
 
     fn serialize_event(&self) -> String {
-        let event = DispatchedEvent::new(
+        let mut event = DispatchedEvent::new(
             serde_json::to_string(self).expect("could not serialize event"),
             Self::event(),
         );
+        inject_context(&mut event);
 
         serde_json::to_string(&event).unwrap()
     }
 
     /// Subject to this event
-    async fn subscribe<H: EventHandler + Default>()
+    ///
+    /// The handler stays registered for as long as the returned handle is alive
+    async fn subscribe<H: EventHandler + Default>() -> SubscriptionHandle
     where
         Self: Sized,
     {
-        crate::setup().await;
-
-        let event: String = Self::event();
-        let the_handler = H::default().to_handler();
-
-        let mut subscriber = SubscriberList::new();
-
-        log::trace!(
-            target: LOG_TITLE,
-            "registered handler: {:?}, for event: {:?}",
-            &event,
-            &the_handler.handler_id()
-        );
-
-        subscriber.insert(event, vec![the_handler]);
-        merge_subscribers(subscriber).await;
+        Self::subscribe_with(H::default()).await
     }
 
-    async fn subscribe_with(handler: impl EventHandler) {
-        crate::setup().await;
-
-        let event: String = Self::event();
-        let the_handler = handler.to_handler();
-
-        let mut subscriber = SubscriberList::new();
-
-        log::trace!(
-            target: LOG_TITLE,
-            "registered handler: {:?}, for event: {:?}",
-            &event,
-            &the_handler.handler_id()
-        );
+    /// Subscribe an instance of a handler to this event
+    ///
+    /// # Panics
+    /// When the ordering constraints of the handler form a cycle. See `try_subscribe_with`
+    async fn subscribe_with(handler: impl EventHandler) -> SubscriptionHandle {
+        match subscribe_handler(Self::event(), handler.to_handler()).await {
+            Ok(handle) => handle,
+            Err(error) => panic!("could not subscribe the handler: {}", error),
+        }
+    }
 
-        subscriber.insert(event, vec![the_handler]);
-        merge_subscribers(subscriber).await;
+    /// Subscribe an instance of a handler to this event
+    ///
+    /// Fails when the ordering constraints of the handler form a cycle
+    async fn try_subscribe_with(handler: impl EventHandler) -> Result<SubscriptionHandle, Error> {
+        subscribe_handler(Self::event(), handler.to_handler()).await
     }
 
     async fn subscribe_fn(
         handler: impl Fn(DispatchedEvent) -> BoxFuture<'static, ()> + Send + Sync + 'static,
-    ) {
+    ) -> SubscriptionHandle {
         let wrapper = ClosureHandlerWrapper(handler);
-        Self::subscribe_with(wrapper).await;
+        Self::subscribe_with(wrapper).await
     }
 
     /// Unsubscribe to this event
+    ///
+    /// Every registration of the handler `H` is removed
     async fn unsubscribe<H: EventHandler + Default>() {
         crate::setup().await;
         let the_handler = H::default().to_handler();
 
-        unsubscribe(Self::event(), the_handler.handler_id()).await;
+        unsubscribe(Self::event(), the_handler.handler_id());
     }
+
+    /// Removes every registration with the label from this event
+    async fn unsubscribe_label(label: &str) {
+        crate::setup().await;
+        unsubscribe_label(Self::event(), label);
+    }
+
+    /// Removes the specific registration from this event
+    async fn unsubscribe_id(id: &HandlerId) {
+        crate::setup().await;
+        unsubscribe_registration(Self::event(), id.registration());
+    }
+}
+
+/// Registers the handler. The handle is only created once the registration is in the list
+async fn subscribe_handler(
+    event: String,
+    handler: Box<dyn EventHandler>,
+) -> Result<SubscriptionHandle, Error> {
+    crate::setup().await;
+
+    let registration = Registration::new(handler);
+    let handler_id = registration.handler_id();
+
+    let mut subscriber = SubscriberList::new();
+    subscriber.insert(event.clone(), vec![registration]);
+    merge_subscribers(subscriber)?;
+
+    log::trace!(
+        target: LOG_TITLE,
+        "registered handler: {}, for event: {:?}",
+        &handler_id,
+        &event
+    );
+
+    Ok(SubscriptionHandle::new(event, handler_id))
+}
+
+/// Decides whether an event propagates to the remaining handlers
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    /// Call the next handler
+    Continue,
+    /// Do not call the remaining handlers
+    Stop,
 }
 
 /// Event handler must implement this trait
@@ -147,8 +191,62 @@ pub trait EventHandler: Send + Sync + 'static {
     ///
     /// }
     /// ```
+    ///
+    /// Implement `handle_event` as well when the handler decides per event
+    /// whether the event should propagate to the remaining handlers
     async fn handle(&self, event: DispatchedEvent);
 
+    /// Called when an event is ready. The returned outcome decides whether the
+    /// event propagates to the remaining handlers.
+    /// By default `handle` is called and `propagate` decides the outcome.
+    ///
+    /// The dispatcher only calls `handle_event`, through `try_handle_event`. A handler
+    /// that implements it usually implements `handle` by calling it
+    /// ```
+    /// # use orsomafo::{Dispatchable, DispatchedEvent, EventDispatcherBuilder, EventHandler, Outcome};
+    /// # #[tokio::main]
+    /// # async fn main() {
+    ///    #[derive(Clone, serde::Serialize, serde::Deserialize)]
+    ///    struct OrderPlaced {
+    ///        total: u32,
+    ///    }
+    ///    impl Dispatchable for OrderPlaced {}
+    ///
+    ///    struct ValidateOrder;
+    ///
+    ///    #[orsomafo::async_trait]
+    ///    impl EventHandler for ValidateOrder {
+    ///        async fn handle(&self, event: DispatchedEvent) {
+    ///            self.handle_event(event).await;
+    ///        }
+    ///
+    ///        async fn handle_event(&self, event: DispatchedEvent) -> Outcome {
+    ///            match event.the_event::<OrderPlaced>() {
+    ///                Some(order) if order.total > 0 => Outcome::Continue,
+    ///                _ => Outcome::Stop, // Invalid orders are not passed to the other handlers
+    ///            }
+    ///        }
+    ///    }
+    /// # }
+    /// ```
+    async fn handle_event(&self, event: DispatchedEvent) -> Outcome {
+        self.handle(event).await;
+        if self.propagate() {
+            Outcome::Continue
+        } else {
+            Outcome::Stop
+        }
+    }
+
+    /// Called by the dispatcher when an event is ready. By default `handle_event` is called.
+    ///
+    /// Returning an error fails the handler like a panic does: the error hooks are
+    /// called, the handler is listed in `DispatchReport::failed` and the event is
+    /// kept as a dead letter. The remaining handlers are still called
+    async fn try_handle_event(&self, event: DispatchedEvent) -> Result<Outcome, String> {
+        Ok(self.handle_event(event).await)
+    }
+
     fn to_handler(self) -> Box<Self>
     where
         Self: Sized,
@@ -162,11 +260,108 @@ pub trait EventHandler: Send + Sync + 'static {
         std::any::type_name::<Self>().to_string()
     }
 
+    /// An optional label that sets this registration apart from other
+    /// registrations of the same handler
+    fn label(&self) -> Option<String> {
+        None
+    }
+
+    /// Registers this handler with the given label
+    fn with_label(self, label: &str) -> ConfiguredHandler<Self>
+    where
+        Self: Sized,
+    {
+        ConfiguredHandler::new(self).with_label(label)
+    }
+
+    /// Handlers with a higher priority are called first.
+    /// Handlers with the same priority are called in the order they were registered
+    fn priority(&self) -> i32 {
+        0
+    }
+
+    /// Registers this handler with the given priority
+    fn with_priority(self, priority: i32) -> ConfiguredHandler<Self>
+    where
+        Self: Sized,
+    {
+        ConfiguredHandler::new(self).with_priority(priority)
+    }
+
+    /// Labels or names of the handlers this handler must be called before
+    fn runs_before(&self) -> Vec<String> {
+        Vec::new()
+    }
+
+    /// Registers this handler to be called before the handler with the given label or name
+    fn run_before(self, name: &str) -> ConfiguredHandler<Self>
+    where
+        Self: Sized,
+    {
+        ConfiguredHandler::new(self).run_before(name)
+    }
+
+    /// Labels or names of the handlers this handler must be called after
+    fn runs_after(&self) -> Vec<String> {
+        Vec::new()
+    }
+
+    /// Registers this handler to be called after the handler with the given label or name
+    fn run_after(self, name: &str) -> ConfiguredHandler<Self>
+    where
+        Self: Sized,
+    {
+        ConfiguredHandler::new(self).run_after(name)
+    }
+
+    /// Only the events accepted by the filter are passed to this handler
+    fn filter(&self) -> Option<Filter> {
+        None
+    }
+
+    /// Registers this handler with the given filter
+    fn with_filter(self, filter: Filter) -> ConfiguredHandler<Self>
+    where
+        Self: Sized,
+    {
+        ConfiguredHandler::new(self).with_filter(filter)
+    }
+
     /// Executes this handler once and dequeue it if `true` is returned
     fn execute_once(&self) -> bool {
         false
     }
 
+    /// How long this handler stays registered.
+    /// By default `execute_once` decides between `Lifetime::Once` and `Lifetime::Forever`
+    fn lifetime(&self) -> Lifetime {
+        if self.execute_once() {
+            Lifetime::Once
+        } else {
+            Lifetime::Forever
+        }
+    }
+
+    /// Registers this handler with the given lifetime
+    /// ```
+    /// # use orsomafo::{Dispatchable, DispatchedEvent, EventHandler, Lifetime};
+    /// # #[tokio::main]
+    /// # async fn main() {
+    ///    #[derive(Clone, serde::Serialize, serde::Deserialize)]
+    ///    struct MyEvent;
+    ///    impl Dispatchable for MyEvent {}
+    ///
+    ///    let handler = orsomafo::handler_fn(|_| Box::pin(async {}));
+    ///    let _handle = MyEvent::subscribe_with(handler.with_lifetime(Lifetime::times(3))).await;
+    /// # }
+    /// ```
+    fn with_lifetime(self, lifetime: Lifetime) -> ConfiguredHandler<Self>
+    where
+        Self: Sized,
+    {
+        ConfiguredHandler::new(self).with_lifetime(lifetime)
+    }
+
     /// Stops propagating the event to other handlers when `false` is returned
     fn propagate(&self) -> bool {
         true
@@ -185,7 +380,7 @@ mod test {
 
     #[tokio::test]
     async fn test_event_dispatching() {
-        UserCreated::subscribe::<HandleUserCreated>().await;
+        let _handle = UserCreated::subscribe::<HandleUserCreated>().await;
 
         event_dispatcher()
             .dispatch_sync(UserCreated { id: 200 })
@@ -194,7 +389,7 @@ mod test {
 
     #[tokio::test]
     async fn test_event_dispatching_with() {
-        UserCreated::subscribe_with(HandleUserCreated).await;
+        let _handle = UserCreated::subscribe_with(HandleUserCreated).await;
 
         event_dispatcher()
             .dispatch_sync(UserCreated { id: 200 })
@@ -210,7 +405,7 @@ mod test {
         async fn handle(&self, dispatched: DispatchedEvent) {
             let the_event = dispatched.the_event();
 
-            assert_eq!(the_event.is_none(), false);
+            assert!(the_event.is_some());
 
             let event: UserCreated = the_event.unwrap();
             assert_eq!(event.id, 200);
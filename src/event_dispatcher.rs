@@ -1,53 +1,485 @@
 #![allow(dead_code)]
 use crate::{
-    dispatched_event::DispatchedEvent, event::Dispatchable, event_listener::call_event_handlers,
+    causation::{check_chain, CausalHistory, CausalLink},
+    clock::Clock,
+    dead_letter::{DeadLetter, DeadLetters},
+    dispatch_builder::DispatchBuilder,
+    dispatch_report::DispatchReport,
+    dispatched_event::DispatchedEvent,
+    error::Error,
+    event::Dispatchable,
+    event_listener::{
+        call_event_handlers, call_registrations, has_subscribers, registration_of, registry,
+        Queued, LOG_TITLE,
+    },
+    handler_id::HandlerId,
+    hooks::Hooks,
+    id_generator::IdGenerator,
+    middleware::{on_dispatch, Middleware},
+    namespace::Namespace,
+    pause::{Hold, Paused},
+    propagation::inject_context,
+    registry::Registry,
+    stats::{Metrics, Stats},
+    subscriber_group::SubscriberGroup,
+    topic::Topic,
+    trace_spans::DispatchSpan,
+    unhandled::{Unhandled, UnhandledPolicy},
     EventDispatcherBuilder,
 };
-use std::sync::{Arc, OnceLock};
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, OnceLock,
+    },
+};
 use tokio::sync::mpsc::UnboundedSender;
+use uuid::Uuid;
 
 pub(crate) static EVENT_DISPATCHER: OnceLock<Arc<EventDispatcher>> = OnceLock::new();
 
-#[derive(Debug)]
 pub struct EventDispatcher {
-    sender: UnboundedSender<DispatchedEvent>,
+    sender: UnboundedSender<Queued>,
+    namespaces: Vec<Namespace>,
+    unhandled: Unhandled,
+    history: CausalHistory,
+    max_depth: usize,
+    dead_letters: DeadLetters,
+    paused: Paused,
+    clock: Arc<dyn Clock>,
+    id_generator: Arc<dyn IdGenerator>,
+    middlewares: Vec<Arc<dyn Middleware>>,
+    hooks: Hooks,
+    metrics: Metrics,
+    // The sequence number of the next dispatched event
+    sequence: AtomicU64,
+}
+
+impl std::fmt::Debug for EventDispatcher {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("EventDispatcher")
+            .field("sender", &self.sender)
+            .field("namespaces", &self.namespaces)
+            .field("unhandled", &self.unhandled)
+            .field("history", &self.history)
+            .field("max_depth", &self.max_depth)
+            .field("dead_letters", &self.dead_letters)
+            .field("paused", &self.paused)
+            .field("middlewares", &self.middlewares.len())
+            .field("hooks", &self.hooks)
+            .field("metrics", &self.metrics)
+            .field("sequence", &self.sequence)
+            .finish()
+    }
+}
+
+/// The settings of the dispatcher collected by `EventDispatcherBuilder`
+pub(crate) struct DispatcherSettings {
+    pub(crate) unhandled: Unhandled,
+    pub(crate) history: CausalHistory,
+    pub(crate) max_depth: usize,
+    pub(crate) dead_letters: DeadLetters,
+    pub(crate) paused: Paused,
+    pub(crate) clock: Arc<dyn Clock>,
+    pub(crate) id_generator: Arc<dyn IdGenerator>,
+    pub(crate) middlewares: Vec<Arc<dyn Middleware>>,
+    pub(crate) hooks: Hooks,
 }
 
 impl EventDispatcher {
-    pub(crate) fn new(sender: UnboundedSender<DispatchedEvent>) -> Self {
-        Self { sender }
+    pub(crate) fn new(
+        sender: UnboundedSender<Queued>,
+        namespaces: Vec<Namespace>,
+        settings: DispatcherSettings,
+    ) -> Self {
+        Self {
+            sender,
+            namespaces,
+            unhandled: settings.unhandled,
+            history: settings.history,
+            max_depth: settings.max_depth,
+            dead_letters: settings.dead_letters,
+            paused: settings.paused,
+            clock: settings.clock,
+            id_generator: settings.id_generator,
+            middlewares: settings.middlewares,
+            hooks: settings.hooks,
+            metrics: Metrics::default(),
+            sequence: AtomicU64::new(1),
+        }
+    }
+
+    /// Queues the event on its namespace, or on the default queue
+    pub(crate) fn send(&self, mut event: DispatchedEvent) -> Result<(), Error> {
+        on_dispatch(&self.middlewares, &mut event)?;
+        self.check(&event)?;
+        self.reject_unhandled(&event)?;
+        let dispatch = self.stamp(&mut event);
+        self.metrics.dispatched(event.name_ref());
+        self.hooks.dispatching(&event);
+        self.enqueue(event, dispatch);
+
+        Ok(())
+    }
+
+    /// Queues the event on its namespace, or on the default queue
+    fn enqueue(&self, event: DispatchedEvent, dispatch: DispatchSpan) {
+        let name = event.name();
+        let sent = match self.namespace_of(&event.topic()) {
+            Some(namespace) => namespace.send(event, dispatch),
+            None => {
+                let sent = self.sender.send((event, dispatch)).is_ok();
+                if sent {
+                    self.metrics.queued();
+                }
+                sent
+            }
+        };
+
+        if !sent {
+            self.metrics.dropped(&name);
+        }
+    }
+
+    /// Calls the handlers of the event in the current thread
+    pub(crate) async fn call(&self, mut event: DispatchedEvent) -> Result<DispatchReport, Error> {
+        on_dispatch(&self.middlewares, &mut event)?;
+        self.check(&event)?;
+        self.check_paused(&event)?;
+        self.reject_unhandled(&event)?;
+        let dispatch = self.stamp(&mut event);
+        self.metrics.dispatched(event.name_ref());
+        self.hooks.dispatching(&event);
+        Ok(call_event_handlers(event, dispatch).await)
+    }
+
+    /// Refuses to handle the events in the current thread while their name is paused
+    fn check_paused(&self, event: &DispatchedEvent) -> Result<(), Error> {
+        if self.paused.is_paused(event.name_ref()) {
+            return Err(Error::Paused {
+                event: event.name(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Marks the event as dispatched by this dispatcher, and opens the span of
+    /// the dispatching
+    fn stamp(&self, event: &mut DispatchedEvent) -> DispatchSpan {
+        event.set_dispatched_at(self.clock.now());
+        event.set_sequence(self.sequence.fetch_add(1, Ordering::SeqCst));
+        inject_context(event);
+        DispatchSpan::open(event)
+    }
+
+    /// Refuses the events that no handler would be called for, when `UnhandledPolicy::Reject` is used
+    fn reject_unhandled(&self, event: &DispatchedEvent) -> Result<(), Error> {
+        if self.unhandled.policy() == UnhandledPolicy::Reject && !has_subscribers(event) {
+            self.unhandled.count(event.name_ref());
+            return Err(Error::NoSubscribers {
+                event: event.name(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Refuses the events that would loop or go deeper than allowed
+    fn check(&self, event: &DispatchedEvent) -> Result<(), Error> {
+        check_chain(event, self.max_depth).map_err(|error| {
+            log::error!(target: LOG_TITLE, "{}", &error);
+            error
+        })
+    }
+
+    pub(crate) fn clock(&self) -> &dyn Clock {
+        self.clock.as_ref()
+    }
+
+    pub(crate) fn id_generator(&self) -> &dyn IdGenerator {
+        self.id_generator.as_ref()
+    }
+
+    pub(crate) fn middlewares(&self) -> &[Arc<dyn Middleware>] {
+        &self.middlewares
+    }
+
+    pub(crate) fn hooks(&self) -> &Hooks {
+        &self.hooks
+    }
+
+    pub(crate) fn metrics(&self) -> &Metrics {
+        &self.metrics
+    }
+
+    /// A snapshot of the registered handlers, per event name and pattern.
+    /// Taking it does not change the registrations, see `RegisteredHandler::has_expired`
+    pub fn registry(&self) -> Registry {
+        registry()
+    }
+
+    /// A snapshot of what the dispatcher has done since it was created
+    pub fn stats(&self) -> Stats {
+        self.metrics.snapshot(self.unhandled.counts())
+    }
+
+    /// Keeps the event that the handler failed to handle
+    pub(crate) fn dead_letter(&self, event: DispatchedEvent, handler: HandlerId, error: String) {
+        self.dead_letters
+            .push(event, handler, error, self.clock.now());
+    }
+
+    /// Holds the event when its name is paused, otherwise gives it back
+    pub(crate) fn hold(&self, event: Queued) -> Hold {
+        self.paused.hold(event)
+    }
+
+    /// Stops handling the events with the name. The events dispatched in the
+    /// meantime, including the ones already queued, are held until the name is
+    /// resumed. Once `EventDispatcherBuilder::max_held_events` are held, the
+    /// next ones are dropped and counted in `Stats::dropped`.
+    /// The events dispatched in the current thread are refused with `Error::Paused`,
+    /// or dropped and counted in `Stats::dropped` by `dispatch_sync` and `dispatch_and_wait`.
+    /// Returns false when the name was already paused
+    pub fn pause(&self, name: &str) -> bool {
+        let paused = self.paused.pause(name);
+        if paused {
+            log::info!(target: LOG_TITLE, "paused event: {:?}", name);
+        }
+        paused
+    }
+
+    /// Queues the events held while the name was paused, in the order they
+    /// were dispatched, and handles the next ones again.
+    /// Returns how many events were held, or `None` when the name was not paused
+    pub fn resume(&self, name: &str) -> Option<usize> {
+        let held = self.paused.resume(name)?;
+        log::info!(
+            target: LOG_TITLE,
+            "resumed event: {:?}, releasing {} held event(s)",
+            name,
+            held.len()
+        );
+
+        let count = held.len();
+        for (event, dispatch) in held {
+            self.enqueue(event, dispatch);
+        }
+        Some(count)
+    }
+
+    pub fn is_paused(&self, name: &str) -> bool {
+        self.paused.is_paused(name)
+    }
+
+    /// The paused event names with the number of events held for each
+    pub fn paused(&self) -> HashMap<String, usize> {
+        self.paused.counts()
+    }
+
+    /// The recent events that a handler failed to handle, oldest first.
+    /// See `EventDispatcherBuilder::dead_letters`
+    pub fn dead_letters(&self) -> Vec<DeadLetter> {
+        self.dead_letters.all()
+    }
+
+    pub fn dead_letter_by_id(&self, id: u64) -> Option<DeadLetter> {
+        self.dead_letters.get(id)
+    }
+
+    /// Removes the dead letter without dispatching its event again
+    pub fn discard_dead_letter(&self, id: u64) -> Option<DeadLetter> {
+        self.dead_letters.take(id)
+    }
+
+    /// Removes the dead letter and calls the handler that failed again in the
+    /// current thread, with the same event ID, data and headers. The other
+    /// handlers of the event do not receive it. A new dead letter is kept if the
+    /// handler fails again. The redrive is counted in `HandlerStats::retries`
+    /// rather than as a dispatched event
+    ///
+    /// The dead letter is kept when the event can not be dispatched, when its
+    /// name is paused, or with `Error::HandlerNotFound` when the handler is no
+    /// longer registered for the event or is disabled
+    pub async fn redrive(&self, id: u64) -> Result<DispatchReport, Error> {
+        let letter = self
+            .dead_letters
+            .take(id)
+            .ok_or(Error::DeadLetterNotFound { id })?;
+        let result = self.redrive_letter(&letter).await;
+        if result.is_err() {
+            self.dead_letters.restore(letter);
+        }
+        result
+    }
+
+    async fn redrive_letter(&self, letter: &DeadLetter) -> Result<DispatchReport, Error> {
+        let mut event = letter.event().clone();
+        on_dispatch(&self.middlewares, &mut event)?;
+        self.check(&event)?;
+        self.check_paused(&event)?;
+        let candidate =
+            registration_of(&event, letter.handler()).ok_or_else(|| Error::HandlerNotFound {
+                event: event.name(),
+                handler: letter.handler().clone(),
+            })?;
+        let dispatch = self.stamp(&mut event);
+        self.metrics.handler_retried(letter.handler());
+        self.hooks.dispatching(&event);
+        Ok(call_registrations(event, dispatch, vec![candidate]).await)
+    }
+
+    pub(crate) fn unhandled(&self) -> &Unhandled {
+        &self.unhandled
+    }
+
+    pub(crate) fn history(&self) -> &CausalHistory {
+        &self.history
+    }
+
+    /// The chain of events that led to the event, starting with the root event
+    /// and ending with the event itself. Only the recently handled events are
+    /// kept, see `EventDispatcherBuilder::causal_history`
+    pub fn causal_chain(&self, event_id: Uuid) -> Vec<CausalLink> {
+        self.history.chain(event_id)
+    }
+
+    /// The recently handled events that share the correlation ID, oldest first
+    pub fn correlated(&self, correlation_id: Uuid) -> Vec<CausalLink> {
+        self.history.correlated(correlation_id)
+    }
+
+    /// The number of events that were not handled by any handler, per event name
+    pub fn unhandled_counts(&self) -> HashMap<String, u64> {
+        self.unhandled.counts()
+    }
+
+    /// The most specific namespace the topic is within
+    fn namespace_of(&self, topic: &Topic) -> Option<&Namespace> {
+        self.namespaces
+            .iter()
+            .filter(|namespace| topic.is_within(namespace.topic()))
+            .max_by_key(|namespace| namespace.topic().segments().len())
+    }
+
+    /// The events recently dispatched under the namespace, oldest first.
+    /// Events are only kept when the namespace is configured with a retention
+    pub fn retained(&self, namespace: &str) -> Vec<DispatchedEvent> {
+        let topic = Topic::parse(namespace);
+        self.namespaces
+            .iter()
+            .find(|n| n.topic() == &topic)
+            .map(|n| n.retained())
+            .unwrap_or_default()
+    }
+
+    /// Prepares the event for dispatching. Use it to attach headers to the event
+    pub fn event<T: Dispatchable>(&self, event: T) -> DispatchBuilder<'_> {
+        DispatchBuilder::new(self, event)
     }
 
     /// Dispatches the event
     pub fn dispatch<T: Dispatchable>(&self, event: T) {
+        _ = self.try_dispatch(event);
+    }
+
+    /// Dispatches the event.
+    /// Fails when the event has no subscribers and `UnhandledPolicy::Reject` is used,
+    /// when a middleware rejects the event, or when the event would be dispatched
+    /// in a cycle or deeper than allowed
+    pub fn try_dispatch<T: Dispatchable>(&self, event: T) -> Result<(), Error> {
         let event = DispatchedEvent::new(
             serde_json::to_string(&event).expect("could not serialize event"),
             T::event(),
         );
-        _ = self.sender.send(event);
+        self.send(event)
     }
 
     pub fn dispatch_str(&self, name: &str, event: impl Dispatchable) {
+        _ = self.try_dispatch_str(name, event);
+    }
+
+    /// Dispatches the event with the given name.
+    /// Fails when the event has no subscribers and `UnhandledPolicy::Reject` is used
+    pub fn try_dispatch_str(&self, name: &str, event: impl Dispatchable) -> Result<(), Error> {
         let event = DispatchedEvent::new(
             serde_json::to_string(&event).expect("could not serialize event"),
             name.to_string(),
         );
-        _ = self.sender.send(event);
+        self.send(event)
     }
 
     pub fn dispatch_json(&self, event: &str) {
-        if let Ok(dispatched_event) = serde_json::from_str::<DispatchedEvent>(event) {
-            _ = self.sender.send(dispatched_event);
-        }
+        _ = self.try_dispatch_json(event);
+    }
+
+    /// Dispatches a serialized `DispatchedEvent` and returns its ID.
+    /// Fails when the JSON is not a dispatched event, or for the same reasons as `try_dispatch`
+    pub fn try_dispatch_json(&self, event: &str) -> Result<Uuid, Error> {
+        let dispatched_event = serde_json::from_str::<DispatchedEvent>(event).map_err(|error| {
+            Error::InvalidEvent {
+                reason: error.to_string(),
+            }
+        })?;
+        let id = dispatched_event.id();
+        self.send(dispatched_event)?;
+        Ok(id)
     }
 
     /// Dispatches the event in the current thread
     pub async fn dispatch_sync<T: Dispatchable + Send + Sync + 'static>(&self, event: T) {
+        if let Err(error) = self.try_dispatch_and_wait(event).await {
+            self.drop_paused(&error);
+        }
+    }
+
+    /// Dispatches the event in the current thread and reports how it was handled.
+    /// The report is empty when the event could not be dispatched
+    pub async fn dispatch_and_wait<T: Dispatchable + Send + Sync + 'static>(
+        &self,
+        event: T,
+    ) -> DispatchReport {
         let event = DispatchedEvent::new(
             serde_json::to_string(&event).expect("could not serialize event"),
             T::event(),
         );
-        call_event_handlers(event).await;
+        let report = DispatchReport::new(event.id(), event.name());
+        self.call(event).await.unwrap_or_else(|error| {
+            self.drop_paused(&error);
+            report
+        })
+    }
+
+    /// Counts the events refused while their name is paused as dropped, for the
+    /// dispatching methods that do not return the error
+    fn drop_paused(&self, error: &Error) {
+        if let Error::Paused { event } = error {
+            log::warn!(
+                target: LOG_TITLE,
+                "dropped event: {:?} dispatched in the current thread, the name is paused",
+                event
+            );
+            self.metrics.dropped(event);
+        }
+    }
+
+    /// Dispatches the event in the current thread and reports how it was handled.
+    /// Fails for the same reasons as `try_dispatch`, and with `Error::Paused`
+    /// while the event name is paused
+    pub async fn try_dispatch_and_wait<T: Dispatchable + Send + Sync + 'static>(
+        &self,
+        event: T,
+    ) -> Result<DispatchReport, Error> {
+        let event = DispatchedEvent::new(
+            serde_json::to_string(&event).expect("could not serialize event"),
+            T::event(),
+        );
+        self.call(event).await
+    }
+
+    /// Returns the group of handlers registered by the `Subscriber` with the given name
+    pub fn group(&self, name: &str) -> SubscriberGroup {
+        SubscriberGroup::new(name.to_string())
     }
 }
 
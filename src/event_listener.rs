@@ -1,31 +1,133 @@
 #![allow(dead_code)]
 use crate::{
+    causation::with_cause,
+    clock,
     closure_handler_wrapper::ClosureHandlerWrapper,
+    dispatch_report::DispatchReport,
     dispatched_event::DispatchedEvent,
-    event::{Dispatchable, EventHandler},
+    error::Error,
+    event::{Dispatchable, EventHandler, Outcome},
+    event_dispatcher::EVENT_DISPATCHER,
+    filter::Filter,
+    handler_id::HandlerId,
+    hooks::Hooks,
+    lifetime::Lifetime,
+    middleware::{Next, Terminal},
+    ordering::execution_order,
+    pattern::{is_dotted_pattern, is_pattern, PatternTrie},
+    pause::Hold,
+    propagation::with_trace_context,
+    registry::{RegisteredEvent, RegisteredHandler, Registry},
+    stats,
+    subscriber_group::SubscriberGroup,
+    topic::Topic,
+    trace_spans::{DispatchSpan, HandlerSpan},
 };
-use futures::future::BoxFuture;
-use std::{collections::HashMap, sync::OnceLock};
-use tokio::sync::{mpsc::UnboundedReceiver, RwLock};
+use futures::{future::BoxFuture, FutureExt};
+use std::{
+    any::Any,
+    collections::{HashMap, HashSet},
+    panic::AssertUnwindSafe,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc, Mutex, OnceLock, PoisonError, RwLock,
+    },
+    time::Instant,
+};
+use tokio::sync::mpsc::UnboundedReceiver;
 
 pub(crate) const LOG_TITLE: &str = "orsomafo";
-pub(crate) type SubscriberList = HashMap<String, Vec<Box<dyn EventHandler>>>;
+pub(crate) type SubscriberList = HashMap<String, Vec<Registration>>;
+/// An event in a queue, with the span it was dispatched in
+pub(crate) type Queued = (DispatchedEvent, DispatchSpan);
 
 // List of registered subscribers/listeners
 static REGISTERED_SUBSCRIBERS: OnceLock<RwLock<SubscriberList>> = OnceLock::new();
 
-#[derive(Default)]
+// Index of the keys in the registered list that are patterns
+static REGISTERED_PATTERNS: OnceLock<Mutex<PatternTrie>> = OnceLock::new();
+
+static NEXT_REGISTRATION_ID: AtomicU64 = AtomicU64::new(1);
+static NEXT_SUBSCRIBER_ID: AtomicU64 = AtomicU64::new(1);
+
+/// A handler together with the unique ID of its registration
+pub(crate) struct Registration {
+    pub(crate) id: u64,
+    pub(crate) label: Option<String>,
+    pub(crate) group: Option<String>,
+    pub(crate) enabled: bool,
+    pub(crate) priority: i32,
+    pub(crate) run_before: Vec<String>,
+    pub(crate) run_after: Vec<String>,
+    pub(crate) lifetime: Lifetime,
+    pub(crate) calls: u32,
+    pub(crate) filter: Option<Filter>,
+    pub(crate) handler: Arc<dyn EventHandler>,
+}
+
+impl Registration {
+    pub(crate) fn new(handler: Box<dyn EventHandler>) -> Self {
+        Self {
+            id: NEXT_REGISTRATION_ID.fetch_add(1, Ordering::Relaxed),
+            label: handler.label(),
+            group: None,
+            enabled: true,
+            priority: handler.priority(),
+            run_before: handler.runs_before(),
+            run_after: handler.runs_after(),
+            lifetime: handler.lifetime(),
+            calls: 0,
+            filter: handler.filter(),
+            handler: Arc::from(handler),
+        }
+    }
+
+    /// Returns true if the registration's label or handler name matches
+    pub(crate) fn answers_to(&self, name: &str) -> bool {
+        self.label.as_deref() == Some(name) || self.handler.handler_id() == name
+    }
+
+    pub(crate) fn handler_id(&self) -> HandlerId {
+        HandlerId::new(self.id, self.handler.handler_id(), self.label.clone())
+    }
+}
+
+/// A group of handlers that are registered, enabled, disabled and removed as a unit
 pub struct Subscriber {
+    pub(crate) name: String,
     pub(crate) subscribers: SubscriberList,
 }
 
+impl Default for Subscriber {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Subscriber {
+    /// Creates an unnamed subscriber. The group can only be managed via the
+    /// `SubscriberGroup` returned by `build`
     pub fn new() -> Self {
+        Self::named(&format!(
+            "subscriber#{}",
+            NEXT_SUBSCRIBER_ID.fetch_add(1, Ordering::Relaxed)
+        ))
+    }
+
+    /// Creates a subscriber with the given group name.
+    /// The group can later be managed via `EventDispatcher::group`
+    pub fn named(name: &str) -> Self {
         Self {
+            name: name.to_string(),
             subscribers: SubscriberList::new(),
         }
     }
 
+    /// The name of this subscriber's group
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
     pub fn listen_fn<E: Dispatchable>(
         self,
         handler: impl Fn(DispatchedEvent) -> BoxFuture<'static, ()> + Send + Sync + 'static,
@@ -48,24 +150,26 @@ impl Subscriber {
     }
 
     fn register(mut self, event_name: String, handler: Box<dyn EventHandler>) -> Self {
-        if self.subscribers.get(&event_name).is_none() {
+        if !self.subscribers.contains_key(&event_name) {
             self.subscribers.insert(event_name.clone(), Vec::new());
         }
 
+        let mut registration = Registration::new(handler);
+        registration.group = Some(self.name.clone());
         if let Some(collection) = self.subscribers.get_mut(&event_name) {
             log::trace!(
                 target: LOG_TITLE,
-                "registered handler: {:?}, for event: {:?}",
-                &event_name,
-                &handler.handler_id()
+                "registered handler: {}, for event: {:?}",
+                registration.handler_id(),
+                &event_name
             );
 
-            collection.push(handler);
+            collection.push(registration);
         } else {
             log::error!(
-                "could not register handler: {:?}, for event: {:?}",
-                &event_name,
-                &handler.handler_id()
+                "could not register handler: {}, for event: {:?}",
+                registration.handler_id(),
+                &event_name
             );
         }
 
@@ -73,111 +177,703 @@ impl Subscriber {
     }
 
     /// Apply listeners to the event listeners queue
-    pub async fn build(self) {
+    ///
+    /// The returned group can be used to disable, enable or remove all of the listeners at once.
+    ///
+    /// # Panics
+    /// When the ordering constraints of the listeners form a cycle. See `try_build`
+    pub async fn build(self) -> SubscriberGroup {
+        match self.try_build().await {
+            Ok(group) => group,
+            Err(error) => panic!("could not build the subscriber: {}", error),
+        }
+    }
+
+    /// Apply listeners to the event listeners queue
+    ///
+    /// None of the listeners are registered when their ordering constraints form a cycle
+    pub async fn try_build(self) -> Result<SubscriberGroup, Error> {
         crate::setup().await;
-        merge_subscribers(self.subscribers).await;
+        merge_subscribers(self.subscribers)?;
+        Ok(SubscriberGroup::new(self.name))
     }
 }
 
 pub(crate) struct EventListener {
-    chan_rev: UnboundedReceiver<DispatchedEvent>,
+    chan_rev: UnboundedReceiver<Queued>,
 }
 
 impl EventListener {
-    pub async fn new(
-        subscribers: SubscriberList,
-        receiver: UnboundedReceiver<DispatchedEvent>,
-    ) -> Self {
-        merge_subscribers(subscribers).await;
+    pub fn new(receiver: UnboundedReceiver<Queued>) -> Self {
         Self { chan_rev: receiver }
     }
 
     pub async fn receive(&mut self) {
-        while let Some(event) = self.chan_rev.recv().await {
-            call_event_handlers(event).await;
+        while let Some((event, dispatch)) = self.chan_rev.recv().await {
+            stats::record(|metrics| metrics.dequeued());
+            handle_queued(event, dispatch).await;
         }
     }
 }
 
-pub(crate) async fn merge_subscribers(subscribers: SubscriberList) {
+/// Adds the subscribers to the registered list.
+/// Nothing is added when the ordering constraints of any of the handlers form a cycle
+pub(crate) fn merge_subscribers(subscribers: SubscriberList) -> Result<(), Error> {
     let lock = REGISTERED_SUBSCRIBERS.get_or_init(|| RwLock::new(SubscriberList::new()));
-    let mut list = lock.write().await;
+    let mut list = lock.write().unwrap_or_else(PoisonError::into_inner);
+
+    let mut orders = HashMap::new();
+    for (name, registrations) in subscribers.iter() {
+        let combined = list
+            .get(name)
+            .into_iter()
+            .flatten()
+            .chain(registrations.iter())
+            .collect::<Vec<&Registration>>();
+
+        match execution_order(&combined) {
+            Ok(order) => {
+                orders.insert(name.clone(), order);
+            }
+            Err(handlers) => {
+                let error = Error::OrderingCycle {
+                    event: name.clone(),
+                    handlers,
+                };
+                log::error!(target: LOG_TITLE, "could not register handlers: {}", &error);
+                return Err(error);
+            }
+        }
+    }
+
     for entry in subscribers {
-        if !list.contains_key(&entry.0) {
-            list.insert(entry.0.clone(), Vec::new());
+        if is_pattern(&entry.0) {
+            REGISTERED_PATTERNS
+                .get_or_init(Default::default)
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .insert(&entry.0);
+        } else if is_dotted_pattern(&entry.0) {
+            log::warn!(
+                target: LOG_TITLE,
+                "event name: {:?} is registered as an exact name, pattern segments are separated by '/'",
+                &entry.0
+            );
         }
-        list.get_mut(&entry.0)
-            .expect("could not get a 'mut' reference to the subs list")
-            .extend(entry.1);
+
+        let existing = list.remove(&entry.0).unwrap_or_default();
+        let mut combined = existing
+            .into_iter()
+            .chain(entry.1)
+            .map(Some)
+            .collect::<Vec<Option<Registration>>>();
+
+        let ordered = orders
+            .remove(&entry.0)
+            .expect("could not get the order of the handlers")
+            .into_iter()
+            .filter_map(|index| combined[index].take())
+            .collect();
+        list.insert(entry.0, ordered);
     }
+
+    Ok(())
 }
 
-pub(crate) async fn unsubscribe(name: String, handler_id: String) {
+/// Removes every registration of the handler with the given name
+pub(crate) fn unsubscribe(name: String, handler_id: String) {
+    unsubscribe_where(name, |r| r.handler.handler_id() == handler_id);
+}
+
+/// Removes every registration with the given label
+pub(crate) fn unsubscribe_label(name: String, label: &str) {
+    unsubscribe_where(name, |r| r.label.as_deref() == Some(label));
+}
+
+fn unsubscribe_where(name: String, predicate: impl Fn(&Registration) -> bool) {
     if let Some(lock) = REGISTERED_SUBSCRIBERS.get() {
-        let mut list = lock.write().await;
+        let mut list = lock.write().unwrap_or_else(PoisonError::into_inner);
         if let Some(subscribers) = list.get_mut(&name) {
-            let mut to_remove = None;
-            for a_subscriber in subscribers.iter().enumerate() {
-                if a_subscriber.1.handler_id() == handler_id {
+            subscribers.retain(|a_subscriber| {
+                if predicate(a_subscriber) {
                     log::trace!(
                         target: LOG_TITLE,
-                        "unsubscribing handler: {:?} from event: {:?}",
-                        &a_subscriber.1.handler_id(),
+                        "unsubscribing handler: {} from event: {:?}",
+                        a_subscriber.handler_id(),
                         &name
                     );
-                    to_remove = Some(a_subscriber.0);
+                    false
+                } else {
+                    true
                 }
+            });
+        }
+        remove_empty(&mut list);
+    }
+}
+
+/// Enables or disables every registration in the group.
+/// Returns the number of registrations that were updated
+pub(crate) fn set_group_enabled(group: &str, enabled: bool) -> usize {
+    let mut total = 0;
+    if let Some(lock) = REGISTERED_SUBSCRIBERS.get() {
+        let mut list = lock.write().unwrap_or_else(PoisonError::into_inner);
+        for (name, subscribers) in list.iter_mut() {
+            for a_subscriber in subscribers
+                .iter_mut()
+                .filter(|r| r.group.as_deref() == Some(group))
+            {
+                log::trace!(
+                    target: LOG_TITLE,
+                    "{} handler: {} for event: {:?}",
+                    if enabled { "enabling" } else { "disabling" },
+                    a_subscriber.handler_id(),
+                    name
+                );
+                a_subscriber.enabled = enabled;
+                total += 1;
             }
+        }
+    }
+    total
+}
+
+/// Returns true when at least one registration in the group is enabled
+pub(crate) fn is_group_enabled(group: &str) -> bool {
+    if let Some(lock) = REGISTERED_SUBSCRIBERS.get() {
+        let list = lock.read().unwrap_or_else(PoisonError::into_inner);
+        return list
+            .values()
+            .flatten()
+            .any(|r| r.enabled && r.group.as_deref() == Some(group));
+    }
+    false
+}
+
+/// Removes every registration in the group
+pub(crate) fn remove_group(group: &str) {
+    if let Some(lock) = REGISTERED_SUBSCRIBERS.get() {
+        let mut list = lock.write().unwrap_or_else(PoisonError::into_inner);
+        for (name, subscribers) in list.iter_mut() {
+            subscribers.retain(|a_subscriber| {
+                if a_subscriber.group.as_deref() == Some(group) {
+                    log::trace!(
+                        target: LOG_TITLE,
+                        "unsubscribing handler: {} from event: {:?}",
+                        a_subscriber.handler_id(),
+                        name
+                    );
+                    false
+                } else {
+                    true
+                }
+            });
+        }
+        remove_empty(&mut list);
+    }
+}
+
+/// Removes the registration with the given ID
+pub(crate) fn unsubscribe_registration(name: String, id: u64) {
+    if let Some(lock) = REGISTERED_SUBSCRIBERS.get() {
+        let mut list = lock.write().unwrap_or_else(PoisonError::into_inner);
+        remove_registration(&mut list, &name, id);
+    }
+}
+
+fn remove_registration(list: &mut SubscriberList, name: &str, id: u64) {
+    if let Some(subscribers) = list.get_mut(name) {
+        if let Some(index) = subscribers.iter().position(|r| r.id == id) {
+            log::trace!(
+                target: LOG_TITLE,
+                "unsubscribing handler: {} from event: {:?}",
+                subscribers[index].handler_id(),
+                name
+            );
+            subscribers.remove(index);
+        }
+    }
+    remove_empty(list);
+}
 
-            if let Some(index) = to_remove {
-                subscribers.remove(index);
+/// Removes the names left without registrations, and their patterns from the index
+fn remove_empty(list: &mut SubscriberList) {
+    list.retain(|name, subscribers| {
+        if !subscribers.is_empty() {
+            return true;
+        }
+        if is_pattern(name) {
+            if let Some(patterns) = REGISTERED_PATTERNS.get() {
+                patterns
+                    .lock()
+                    .unwrap_or_else(PoisonError::into_inner)
+                    .remove(name);
             }
         }
+        false
+    });
+}
+
+/// Handles an event taken from a queue, or holds it while its name is paused
+pub(crate) async fn handle_queued(event: DispatchedEvent, dispatch: DispatchSpan) {
+    let Some(dispatcher) = EVENT_DISPATCHER.get() else {
+        call_event_handlers(event, dispatch).await;
+        return;
+    };
+
+    let name = event.name();
+    match dispatcher.hold((event, dispatch)) {
+        Hold::Released((event, dispatch)) => {
+            call_event_handlers(event, dispatch).await;
+        }
+        Hold::Held => log::trace!(target: LOG_TITLE, "held event of paused name: {:?}", &name),
+        Hold::Dropped => {
+            log::warn!(
+                target: LOG_TITLE,
+                "dropped event of paused name: {:?}, too many events are held",
+                &name
+            );
+            dispatcher.metrics().dropped(&name);
+        }
     }
 }
 
-pub(crate) async fn call_event_handlers(event: DispatchedEvent) {
+pub(crate) async fn call_event_handlers(
+    event: DispatchedEvent,
+    dispatch: DispatchSpan,
+) -> DispatchReport {
+    let candidates = registrations_for(&event);
+    call_registrations(event, dispatch, candidates).await
+}
+
+/// Calls the handlers of the registrations, in order
+pub(crate) async fn call_registrations(
+    mut event: DispatchedEvent,
+    dispatch: DispatchSpan,
+    candidates: Vec<Candidate>,
+) -> DispatchReport {
     let name = event.name();
+    let mut report = DispatchReport::new(event.id(), name.clone());
     log::trace!(
         target: LOG_TITLE,
         "received dispatched event: {:?}",
         &name
     );
-    if let Some(lock) = REGISTERED_SUBSCRIBERS.get() {
-        let mut list = lock.write().await;
-        if let Some(subscribers) = list.get_mut(&name) {
-            let mut to_remove = Vec::new();
-            for a_subscriber in subscribers.iter().enumerate() {
+
+    let dispatcher = EVENT_DISPATCHER.get();
+    event.set_handled_at(clock::now());
+    let default_hooks = Hooks::default();
+    let hooks = dispatcher.map_or(&default_hooks, |dispatcher| dispatcher.hooks());
+    let middlewares = dispatcher
+        .map(|dispatcher| dispatcher.middlewares())
+        .unwrap_or_default();
+
+    if let Some(dispatcher) = dispatcher {
+        dispatcher.history().record(&event);
+    }
+
+    // The handlers are called without holding the lock on the list. Each
+    // registration is reserved once the middlewares reach its handler
+    for candidate in candidates {
+        if candidate
+            .filter
+            .is_some_and(|filter| !filter.accepts(&event))
+        {
+            continue;
+        }
+
+        let handler_id = candidate.handler_id;
+        log::trace!(
+            target: LOG_TITLE,
+            "calling handler: {}, for event: {:?}",
+            &handler_id,
+            &name
+        );
+
+        let started = Instant::now();
+        let terminal = Reserving::new(&candidate.key, candidate.id, &handler_id, hooks);
+        let next = Next::new(middlewares, &handler_id, &terminal);
+        let span = HandlerSpan::new(&dispatch, &event, &handler_id);
+        let handling = span.instrument(with_trace_context(next.run(event.clone()), &event));
+        let result = AssertUnwindSafe(with_cause(&event, handling))
+            .catch_unwind()
+            .await;
+        let took = started.elapsed();
+
+        let reached = terminal.reached();
+        let reservation = terminal.into_reservation();
+        let called = reservation.as_ref().is_some_and(|r| r.handler.is_some());
+        let (outcome, failure) = match result {
+            Ok(outcome) => (outcome, reservation.as_ref().and_then(|r| r.error.clone())),
+            Err(panic) => (Outcome::Continue, Some(panic_message(panic.as_ref()))),
+        };
+        span.record(
+            match (&failure, called, outcome) {
+                (Some(_), _, _) => "failed",
+                (None, false, _) => "skipped",
+                (None, true, Outcome::Continue) => "continue",
+                (None, true, Outcome::Stop) => "stop",
+            },
+            took,
+        );
+
+        if called {
+            hooks.handled(&event, &handler_id, took);
+            stats::record(|metrics| metrics.handler_called(&handler_id, took, failure.is_some()));
+            if failure.is_none() {
+                report.record(handler_id.clone());
+            }
+        } else if !reached && failure.is_none() {
+            log::trace!(
+                target: LOG_TITLE,
+                "handler: {}, was not reached for event: {:?}",
+                &handler_id,
+                &name
+            );
+            report.skip(handler_id.clone());
+        }
+        if let Some(message) = failure {
+            log::error!(
+                target: LOG_TITLE,
+                "handler: {}, failed to handle event: {:?}, {}",
+                &handler_id,
+                &name,
+                &message
+            );
+            hooks.failed(&event, &handler_id, &message);
+            if let Some(dispatcher) = dispatcher {
+                dispatcher.dead_letter(event.clone(), handler_id.clone(), message);
+            }
+            report.fail(handler_id.clone());
+        }
+
+        if reservation.is_some_and(|r| r.removed) {
+            hooks.removed(&handler_id);
+            stats::record(|metrics| metrics.handler_removed());
+        }
+
+        if outcome == Outcome::Stop {
+            if called {
                 log::trace!(
                     target: LOG_TITLE,
-                    "calling handler: {:?}, for event: {:?}",
-                    &a_subscriber.1.handler_id(),
+                    "handler: {}, stopped the propagation of event: {:?}",
+                    &handler_id,
                     &name
                 );
+                report.stop(handler_id);
+            } else {
+                log::trace!(
+                    target: LOG_TITLE,
+                    "a middleware of handler: {}, stopped the propagation of event: {:?}",
+                    &handler_id,
+                    &name
+                );
+                report.stop_before(handler_id);
+            }
+            break;
+        }
+    }
 
-                a_subscriber.1.handle(event.clone()).await;
-                if a_subscriber.1.execute_once() {
-                    to_remove.push(a_subscriber.0);
-                }
+    // A handler that failed still counts as a subscriber of the event
+    if !report.handled_by().is_empty() || !report.failed().is_empty() {
+        stats::record(|metrics| metrics.handled(&name));
+    } else if let Some(dispatcher) = dispatcher {
+        let handled = with_cause(&event, dispatcher.unhandled().handle(event.clone())).await;
+        if let Err((handler_id, message)) = handled {
+            log::error!(
+                target: LOG_TITLE,
+                "fallback handler: {}, failed to handle event: {:?}, {}",
+                &handler_id,
+                &name,
+                &message
+            );
+            dispatcher.dead_letter(event.clone(), handler_id, message);
+        }
+    }
 
-                if a_subscriber.1.propagate() == false {
-                    break;
-                }
-            }
+    report
+}
+
+/// The message of a panic payload
+pub(crate) fn panic_message(panic: &(dyn Any + Send)) -> String {
+    if let Some(message) = panic.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = panic.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "handler panicked".to_string()
+    }
+}
+
+/// A registration that may be called for an event
+pub(crate) struct Candidate {
+    key: String,
+    id: u64,
+    handler_id: HandlerId,
+    filter: Option<Filter>,
+}
 
-            if !to_remove.is_empty() {
-                for index in to_remove.into_iter() {
-                    subscribers.remove(index);
+/// The enabled registrations to call for the event in calling order.
+/// A registration is only called once, even when the event name is itself a
+/// pattern and the registration is found under both the exact name and the pattern
+fn registrations_for(event: &DispatchedEvent) -> Vec<Candidate> {
+    let Some(lock) = REGISTERED_SUBSCRIBERS.get() else {
+        return Vec::new();
+    };
+
+    let list = lock.read().unwrap_or_else(PoisonError::into_inner);
+    let mut seen = HashSet::new();
+    keys_for(event.name_ref())
+        .into_iter()
+        .flat_map(|key| {
+            list.get(&key)
+                .into_iter()
+                .flatten()
+                .filter(|r| r.enabled)
+                .map(|r| Candidate {
+                    key: key.clone(),
+                    id: r.id,
+                    handler_id: r.handler_id(),
+                    filter: r.filter.clone(),
+                })
+                .collect::<Vec<Candidate>>()
+        })
+        .filter(|candidate| seen.insert(candidate.id))
+        .collect()
+}
+
+/// The enabled registration of the handler, when it would be called for the event
+pub(crate) fn registration_of(event: &DispatchedEvent, handler: &HandlerId) -> Option<Candidate> {
+    registrations_for(event)
+        .into_iter()
+        .find(|candidate| candidate.id == handler.registration())
+}
+
+/// Returns true if at least one enabled registration would be called for the event.
+/// The filters are called outside of the lock, as they may dispatch or subscribe
+pub(crate) fn has_subscribers(event: &DispatchedEvent) -> bool {
+    let Some(lock) = REGISTERED_SUBSCRIBERS.get() else {
+        return false;
+    };
+
+    let filters = {
+        let list = lock.read().unwrap_or_else(PoisonError::into_inner);
+        keys_for(event.name_ref())
+            .iter()
+            .filter_map(|key| list.get(key))
+            .flatten()
+            .filter(|r| r.enabled)
+            .map(|r| r.filter.clone())
+            .collect::<Vec<Option<Filter>>>()
+    };
+
+    filters
+        .iter()
+        .any(|filter| filter.as_ref().map_or(true, |f| f.accepts(event)))
+}
+
+/// A snapshot of the registered list. The registrations whose lifetime already
+/// ended are listed as expired, and are removed by the next event they apply to
+pub(crate) fn registry() -> Registry {
+    let Some(lock) = REGISTERED_SUBSCRIBERS.get() else {
+        return Registry::default();
+    };
+
+    let list = lock.read().unwrap_or_else(PoisonError::into_inner);
+    let events = list
+        .iter()
+        .filter(|(_, registrations)| !registrations.is_empty())
+        .map(|(key, registrations)| {
+            let handlers = registrations
+                .iter()
+                .enumerate()
+                .map(|(position, r)| {
+                    RegisteredHandler::new(
+                        r.handler_id(),
+                        position,
+                        r.priority,
+                        r.run_before.clone(),
+                        r.run_after.clone(),
+                        &r.lifetime,
+                        r.calls,
+                        r.enabled,
+                        r.group.clone(),
+                        r.filter.is_some(),
+                    )
+                })
+                .collect();
+            RegisteredEvent::new(key.clone(), is_pattern(key), handlers)
+        })
+        .collect();
+
+    Registry::new(events)
+}
+
+/// The keys in the registered list that apply to the event name, in calling order.
+///
+/// The exact name comes first, followed by the parent topics, closest parent
+/// first, and then the matching patterns. See the `topic` and `pattern` modules
+fn keys_for(name: &str) -> Vec<String> {
+    let mut keys = vec![name.to_string()];
+    keys.extend(Topic::parse(name).ancestors().iter().map(Topic::to_string));
+    keys.extend(
+        REGISTERED_PATTERNS
+            .get()
+            .map(|patterns| {
+                patterns
+                    .lock()
+                    .unwrap_or_else(PoisonError::into_inner)
+                    .matching(name)
+            })
+            .unwrap_or_default(),
+    );
+    keys
+}
+
+/// A registration reserved for a call
+struct Reservation {
+    /// `None` when the lifetime of the registration ended before the call
+    handler: Option<Arc<dyn EventHandler>>,
+    /// The registration was removed from the list
+    removed: bool,
+    /// The error returned by the handler
+    error: Option<String>,
+}
+
+/// Ends the middleware chain by reserving the registration and calling its handler.
+/// Nothing is reserved when a middleware does not run the rest of the chain
+struct Reserving<'a> {
+    key: &'a str,
+    id: u64,
+    handler_id: &'a HandlerId,
+    hooks: &'a Hooks,
+    reached: AtomicBool,
+    reservation: Mutex<Option<Reservation>>,
+}
+
+impl<'a> Reserving<'a> {
+    fn new(key: &'a str, id: u64, handler_id: &'a HandlerId, hooks: &'a Hooks) -> Self {
+        Self {
+            key,
+            id,
+            handler_id,
+            hooks,
+            reached: AtomicBool::new(false),
+            reservation: Mutex::new(None),
+        }
+    }
+
+    /// Returns true if the middlewares ran the rest of the chain
+    fn reached(&self) -> bool {
+        self.reached.load(Ordering::SeqCst)
+    }
+
+    /// The reservation made when the handler was reached
+    fn into_reservation(self) -> Option<Reservation> {
+        self.reservation
+            .into_inner()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl Terminal for Reserving<'_> {
+    fn reach(&self, event: DispatchedEvent) -> BoxFuture<'_, Outcome> {
+        self.reached.store(true, Ordering::SeqCst);
+        let reservation = reserve(self.key, self.id, &event);
+        let handler = reservation.as_ref().and_then(|r| r.handler.clone());
+        *self
+            .reservation
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner) = reservation;
+
+        Box::pin(async move {
+            let Some(handler) = handler else {
+                return Outcome::Continue;
+            };
+
+            self.hooks.handling(&event, self.handler_id);
+            match handler.try_handle_event(event).await {
+                Ok(outcome) => outcome,
+                Err(error) => {
+                    if let Some(reservation) = self
+                        .reservation
+                        .lock()
+                        .unwrap_or_else(PoisonError::into_inner)
+                        .as_mut()
+                    {
+                        reservation.error = Some(error);
+                    }
+                    Outcome::Continue
                 }
             }
-        }
+        })
+    }
+}
+
+/// Claims one call of the registration.
+///
+/// Nothing is returned when the registration was removed or is disabled.
+/// A registration that reaches the end of its lifetime is removed, and has
+/// no handler to call when its lifetime ended before this call
+fn reserve(key: &str, id: u64, event: &DispatchedEvent) -> Option<Reservation> {
+    let lock = REGISTERED_SUBSCRIBERS.get()?;
+
+    // The predicate of `Lifetime::UntilEvent` and the clock may subscribe or
+    // dispatch, so the lifetime is checked without holding the lock on the list
+    let lifetime = {
+        let list = lock.read().unwrap_or_else(PoisonError::into_inner);
+        let registration = list.get(key)?.iter().find(|r| r.id == id)?;
+        registration.lifetime.clone()
+    };
+    let ended = lifetime.has_ended(event);
+
+    let mut list = lock.write().unwrap_or_else(PoisonError::into_inner);
+    let subscribers = list.get_mut(key)?;
+    let index = subscribers.iter().position(|r| r.id == id)?;
+    let a_subscriber = &mut subscribers[index];
+    if !a_subscriber.enabled {
+        return None;
+    }
+
+    if !ended {
+        a_subscriber.calls += 1;
+    }
+
+    let handler_id = a_subscriber.handler_id();
+    let handler = (!ended).then(|| a_subscriber.handler.clone());
+    let last_call = a_subscriber
+        .lifetime
+        .max_calls()
+        .is_some_and(|max| a_subscriber.calls >= max);
+
+    let removed = ended || last_call;
+    if removed {
+        log::trace!(
+            target: LOG_TITLE,
+            "handler: {}, reached the end of its lifetime for event: {:?}",
+            &handler_id,
+            event.name_ref()
+        );
+        subscribers.remove(index);
+        remove_empty(&mut list);
     }
+
+    Some(Reservation {
+        handler,
+        removed,
+        error: None,
+    })
 }
 
-#[allow(unused_imports)]
+#[allow(
+    unused_imports,
+    clippy::bool_assert_comparison,
+    clippy::assertions_on_constants
+)]
 mod test {
-    use crate::event_dispatcher;
+    use crate::{causation::check_chain, event_dispatcher, EventDispatcherBuilder};
     use async_trait::async_trait;
+    use std::time::Duration;
 
     use super::*;
 
@@ -199,7 +895,9 @@ mod test {
 
     #[tokio::test]
     async fn test_unsubscribing() {
-        UserCreated2::subscribe::<HandleUserCreated2>().await;
+        UserCreated2::subscribe::<HandleUserCreated2>()
+            .await
+            .detach();
 
         UserCreated2::unsubscribe::<HandleUserCreated2>().await;
         event_dispatcher()
@@ -207,6 +905,858 @@ mod test {
             .await;
     }
 
+    #[tokio::test]
+    async fn test_subscription_handle() {
+        static CALLED: AtomicU64 = AtomicU64::new(0);
+        let counter = || {
+            |_: DispatchedEvent| -> BoxFuture<'static, ()> {
+                Box::pin(async {
+                    CALLED.fetch_add(1, Ordering::SeqCst);
+                })
+            }
+        };
+
+        let dropped = UserCreated3::subscribe_fn(counter()).await;
+        let unsubscribed = UserCreated3::subscribe_fn(counter()).await;
+        let detached = UserCreated3::subscribe_fn(counter()).await;
+        assert_ne!(dropped.id(), unsubscribed.id());
+
+        drop(dropped);
+        unsubscribed.unsubscribe();
+        detached.detach();
+
+        event_dispatcher()
+            .dispatch_sync(UserCreated3 { id: 1 })
+            .await;
+        assert_eq!(CALLED.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_unique_handler_ids() {
+        static CALLED: AtomicU64 = AtomicU64::new(0);
+
+        #[derive(Default)]
+        struct Counter(u64);
+
+        #[async_trait]
+        impl EventHandler for Counter {
+            async fn handle(&self, _: DispatchedEvent) {
+                CALLED.fetch_add(self.0, Ordering::SeqCst);
+            }
+        }
+
+        let one = UserCreated4::subscribe_with(Counter(1).with_label("one")).await;
+        let ten = UserCreated4::subscribe_with(Counter(10).with_label("ten")).await;
+
+        assert_ne!(one.handler_id(), ten.handler_id());
+        assert_eq!(one.handler_id().name(), ten.handler_id().name());
+        assert_eq!(ten.handler_id().label(), Some("ten"));
+        assert!(ten.handler_id().to_string().contains("[ten]#"));
+
+        one.detach();
+        ten.detach();
+
+        UserCreated4::unsubscribe_label("ten").await;
+        event_dispatcher()
+            .dispatch_sync(UserCreated4 { id: 1 })
+            .await;
+        assert_eq!(CALLED.load(Ordering::SeqCst), 1);
+
+        UserCreated4::unsubscribe::<Counter>().await;
+        event_dispatcher()
+            .dispatch_sync(UserCreated4 { id: 1 })
+            .await;
+        assert_eq!(CALLED.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_subscriber_group() {
+        static CALLED: AtomicU64 = AtomicU64::new(0);
+        let counter = |_: DispatchedEvent| -> BoxFuture<'static, ()> {
+            Box::pin(async {
+                CALLED.fetch_add(1, Ordering::SeqCst);
+            })
+        };
+
+        let group = Subscriber::named("test-group")
+            .listen_fn::<UserCreated5>(counter)
+            .listen_fn::<UserCreated5>(counter)
+            .build()
+            .await;
+        assert_eq!(group.name(), "test-group");
+
+        group.disable();
+        assert!(!event_dispatcher().group("test-group").is_enabled());
+        event_dispatcher()
+            .dispatch_sync(UserCreated5 { id: 1 })
+            .await;
+        assert_eq!(CALLED.load(Ordering::SeqCst), 0);
+
+        event_dispatcher().group("test-group").enable();
+        event_dispatcher()
+            .dispatch_sync(UserCreated5 { id: 1 })
+            .await;
+        assert_eq!(CALLED.load(Ordering::SeqCst), 2);
+
+        group.remove();
+        event_dispatcher()
+            .dispatch_sync(UserCreated5 { id: 1 })
+            .await;
+        assert_eq!(CALLED.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_handler_ordering() {
+        static CALLED: Mutex<Vec<&'static str>> = Mutex::new(Vec::new());
+        let record = |name: &'static str| {
+            crate::handler_fn(move |_| {
+                Box::pin(async move {
+                    CALLED.lock().unwrap().push(name);
+                })
+            })
+        };
+
+        let handles = vec![
+            UserCreated6::subscribe_with(record("low").with_label("low").with_priority(-1)).await,
+            UserCreated6::subscribe_with(record("default").with_label("default")).await,
+            UserCreated6::subscribe_with(record("high").with_label("high").with_priority(10)).await,
+            UserCreated6::subscribe_with(record("first").run_before("high")).await,
+            UserCreated6::subscribe_with(
+                record("last")
+                    .with_label("last")
+                    .with_priority(100)
+                    .run_after("low"),
+            )
+            .await,
+        ];
+
+        let cycle = UserCreated6::try_subscribe_with(
+            record("cycle")
+                .with_label("cycle")
+                .run_before("low")
+                .run_after("last"),
+        )
+        .await;
+        assert!(matches!(cycle, Err(Error::OrderingCycle { .. })));
+
+        let built = EventDispatcherBuilder::new()
+            .listen_with::<UserCreated6>(
+                record("built")
+                    .with_label("built")
+                    .run_before("low")
+                    .run_after("last"),
+            )
+            .try_build()
+            .await;
+        assert!(matches!(built, Err(Error::OrderingCycle { .. })));
+
+        event_dispatcher()
+            .dispatch_sync(UserCreated6 { id: 1 })
+            .await;
+        assert_eq!(
+            *CALLED.lock().unwrap(),
+            vec!["default", "first", "high", "low", "last"]
+        );
+
+        drop(handles);
+    }
+
+    #[tokio::test]
+    async fn test_dynamic_propagation() {
+        struct Validate;
+
+        #[async_trait]
+        impl EventHandler for Validate {
+            async fn handle(&self, dispatched: DispatchedEvent) {
+                self.handle_event(dispatched).await;
+            }
+
+            async fn handle_event(&self, dispatched: DispatchedEvent) -> Outcome {
+                match dispatched.the_event::<UserCreated7>() {
+                    Some(event) if event.id > 0 => Outcome::Continue,
+                    _ => Outcome::Stop,
+                }
+            }
+        }
+
+        let validate = UserCreated7::subscribe_with(Validate.with_priority(1)).await;
+        let next = UserCreated7::subscribe_fn(|_| Box::pin(async {})).await;
+
+        let report = event_dispatcher()
+            .dispatch_and_wait(UserCreated7 { id: 1 })
+            .await;
+        assert_eq!(
+            report.handled_by(),
+            &[validate.handler_id().clone(), next.handler_id().clone()]
+        );
+        assert!(report.stopped_by().is_none());
+
+        let report = event_dispatcher()
+            .dispatch_and_wait(UserCreated7 { id: 0 })
+            .await;
+        assert_eq!(report.handled_by(), &[validate.handler_id().clone()]);
+        assert_eq!(report.stopped_by(), Some(validate.handler_id()));
+    }
+
+    #[tokio::test]
+    async fn test_handler_lifetimes() {
+        static CALLED: Mutex<Vec<&'static str>> = Mutex::new(Vec::new());
+        let record = |name: &'static str| {
+            crate::handler_fn(move |_| {
+                Box::pin(async move {
+                    CALLED.lock().unwrap().push(name);
+                })
+            })
+        };
+
+        let handles = vec![
+            UserCreated8::subscribe_with(record("once-1").with_lifetime(Lifetime::Once)).await,
+            UserCreated8::subscribe_with(record("forever")).await,
+            UserCreated8::subscribe_with(record("once-2").with_lifetime(Lifetime::Once)).await,
+            UserCreated8::subscribe_with(record("twice").with_lifetime(Lifetime::times(2))).await,
+            UserCreated8::subscribe_with(
+                record("expires").with_lifetime(Lifetime::expire_after(Duration::from_secs(3600))),
+            )
+            .await,
+            UserCreated8::subscribe_with(record("expired").with_lifetime(Lifetime::until(
+                chrono::Utc::now() - chrono::Duration::seconds(1),
+            )))
+            .await,
+            UserCreated8::subscribe_with(record("never").with_lifetime(Lifetime::times(0))).await,
+            UserCreated8::subscribe_with(record("until-3").with_lifetime(Lifetime::until_event(
+                |e| e.the_event::<UserCreated8>().unwrap().id >= 3,
+            )))
+            .await,
+        ];
+
+        for id in 1..=3 {
+            event_dispatcher().dispatch_sync(UserCreated8 { id }).await;
+        }
+
+        assert_eq!(
+            *CALLED.lock().unwrap(),
+            vec![
+                "once-1", "forever", "once-2", "twice", "expires", "until-3", // first event
+                "forever", "twice", "expires", "until-3", // second event
+                "forever", "expires", // third event
+            ]
+        );
+
+        drop(handles);
+    }
+
+    #[tokio::test]
+    async fn test_lifetime_predicate_uses_registry() {
+        static CALLED: AtomicU64 = AtomicU64::new(0);
+
+        // The predicate reads the registry while the registration is reserved
+        let _handle = UserCreated23::subscribe_with(
+            crate::handler_fn(|_| {
+                Box::pin(async {
+                    CALLED.fetch_add(1, Ordering::SeqCst);
+                })
+            })
+            .with_lifetime(Lifetime::until_event(|event| {
+                let registered = event_dispatcher()
+                    .registry()
+                    .event(&UserCreated23::event())
+                    .is_some();
+                registered && event.the_event::<UserCreated23>().unwrap().id >= 2
+            })),
+        )
+        .await;
+
+        for id in 1..=3 {
+            event_dispatcher().dispatch_sync(UserCreated23 { id }).await;
+        }
+
+        assert_eq!(CALLED.load(Ordering::SeqCst), 1);
+        assert!(event_dispatcher()
+            .registry()
+            .event(&UserCreated23::event())
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn test_pattern_subscriptions() {
+        static CALLED: Mutex<Vec<&'static str>> = Mutex::new(Vec::new());
+        let record = |name: &'static str| {
+            move |_| -> BoxFuture<'static, ()> {
+                Box::pin(async move {
+                    CALLED.lock().unwrap().push(name);
+                })
+            }
+        };
+
+        EventDispatcherBuilder::new()
+            .listen_str_fn("pattern_test/**", record("many"))
+            .listen_str_fn("pattern_test/user/*", record("one"))
+            .listen_str_fn("pattern_test/user/created", record("exact"))
+            .build()
+            .await;
+
+        let call = |name: &str| {
+            let event = DispatchedEvent::new("{}".to_string(), name.to_string());
+            let dispatch = DispatchSpan::open(&event);
+            call_event_handlers(event, dispatch)
+        };
+        call("pattern_test/user/created").await;
+        call("pattern_test/order/eu/created").await;
+        call("pattern_test").await;
+
+        assert_eq!(
+            *CALLED.lock().unwrap(),
+            vec!["exact", "one", "many", "many"]
+        );
+
+        // A name that is itself a pattern calls its handlers once
+        CALLED.lock().unwrap().clear();
+        call("pattern_test/user/*").await;
+        assert_eq!(*CALLED.lock().unwrap(), vec!["one", "many"]);
+
+        // The pattern is forgotten with its last registration
+        let is_indexed = || {
+            REGISTERED_PATTERNS
+                .get()
+                .unwrap()
+                .lock()
+                .unwrap()
+                .matching("pattern_test/gone/x")
+                .contains(&"pattern_test/gone/*".to_string())
+        };
+        let registration =
+            Registration::new(crate::handler_fn(|_| Box::pin(async {})).to_handler());
+        let id = registration.id;
+        let mut subscribers = SubscriberList::new();
+        subscribers.insert("pattern_test/gone/*".to_string(), vec![registration]);
+        merge_subscribers(subscribers).unwrap();
+        assert!(is_indexed());
+
+        unsubscribe_registration("pattern_test/gone/*".to_string(), id);
+        assert!(!is_indexed());
+    }
+
+    #[tokio::test]
+    async fn test_topic_subscriptions() {
+        static CALLED: Mutex<Vec<&'static str>> = Mutex::new(Vec::new());
+        let record = |name: &'static str| {
+            move |_| -> BoxFuture<'static, ()> {
+                Box::pin(async move {
+                    CALLED.lock().unwrap().push(name);
+                })
+            }
+        };
+
+        EventDispatcherBuilder::new()
+            .listen_str_fn("topic_test", record("root"))
+            .listen_str_fn("topic_test/invoice", record("parent"))
+            .listen_str_fn("topic_test/invoice/paid", record("exact"))
+            .build()
+            .await;
+
+        let call = |name: &str| {
+            let event = DispatchedEvent::new("{}".to_string(), name.to_string());
+            let dispatch = DispatchSpan::open(&event);
+            call_event_handlers(event, dispatch)
+        };
+        call("topic_test/invoice/paid").await;
+        call("topic_test/refund").await;
+
+        assert_eq!(
+            *CALLED.lock().unwrap(),
+            vec!["exact", "parent", "root", "root"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_filtered_subscriptions() {
+        static CALLED: Mutex<Vec<&'static str>> = Mutex::new(Vec::new());
+        let record = |name: &'static str| {
+            crate::handler_fn(move |_| {
+                Box::pin(async move {
+                    CALLED.lock().unwrap().push(name);
+                })
+            })
+        };
+
+        let handles = [
+            UserCreated9::subscribe_with(
+                record("typed")
+                    .with_filter(Filter::event(|e: &UserCreated9| e.id == 2))
+                    .with_lifetime(Lifetime::Once),
+            )
+            .await,
+            UserCreated9::subscribe_with(record("pointer").with_filter(Filter::pointer("/id", 1)))
+                .await,
+        ];
+
+        let first = event_dispatcher()
+            .dispatch_and_wait(UserCreated9 { id: 1 })
+            .await;
+        let second = event_dispatcher()
+            .dispatch_and_wait(UserCreated9 { id: 2 })
+            .await;
+
+        assert_eq!(*CALLED.lock().unwrap(), vec!["pointer", "typed"]);
+        assert_eq!(first.handled_by(), &[handles[1].handler_id().clone()]);
+        assert_eq!(second.handled_by(), &[handles[0].handler_id().clone()]);
+    }
+
+    #[tokio::test]
+    async fn test_unhandled_events() {
+        let _handle = UserCreated10::subscribe_with(
+            crate::handler_fn(|_| Box::pin(async {}))
+                .with_filter(Filter::event(|e: &UserCreated10| e.id > 0)),
+        )
+        .await;
+
+        for id in 0..3 {
+            event_dispatcher()
+                .dispatch_and_wait(UserCreated10 { id })
+                .await;
+        }
+
+        // The first event was filtered out
+        assert_eq!(
+            event_dispatcher()
+                .unhandled_counts()
+                .get(&UserCreated10::event()),
+            Some(&1)
+        );
+
+        // An event that every handler filters out has no subscribers
+        let filtered_out = DispatchedEvent::new(
+            serde_json::to_string(&UserCreated10 { id: 0 }).unwrap(),
+            UserCreated10::event(),
+        );
+        let accepted = DispatchedEvent::new(
+            serde_json::to_string(&UserCreated10 { id: 1 }).unwrap(),
+            UserCreated10::event(),
+        );
+        assert!(!has_subscribers(&filtered_out));
+        assert!(has_subscribers(&accepted));
+
+        assert!(matches!(
+            event_dispatcher().try_dispatch_json("not an event"),
+            Err(Error::InvalidEvent { .. })
+        ));
+
+        static FALLBACK: AtomicU64 = AtomicU64::new(0);
+        let unhandled = crate::unhandled::Unhandled::new(
+            crate::UnhandledPolicy::Warn,
+            Some(Arc::new(crate::handler_fn(|_| {
+                Box::pin(async {
+                    FALLBACK.fetch_add(1, Ordering::SeqCst);
+                })
+            }))),
+        );
+        assert!(unhandled
+            .handle(DispatchedEvent::new("{}".to_string(), "typo".to_string()))
+            .await
+            .is_ok());
+        assert_eq!(FALLBACK.load(Ordering::SeqCst), 1);
+        assert_eq!(unhandled.counts().get("typo"), Some(&1));
+    }
+
+    #[tokio::test]
+    async fn test_event_headers() {
+        static RECEIVED: Mutex<Option<DispatchedEvent>> = Mutex::new(None);
+        let _handle = UserCreated11::subscribe_fn(|event| {
+            Box::pin(async move {
+                *RECEIVED.lock().unwrap() = Some(event);
+            })
+        })
+        .await;
+
+        event_dispatcher()
+            .event(UserCreated11 { id: 1 })
+            .header("tenant", "acme")
+            .header("schema_version", 2)
+            .dispatch_and_wait()
+            .await;
+
+        let received = RECEIVED.lock().unwrap().take().unwrap();
+        assert_eq!(received.header_str("tenant"), Some("acme"));
+        assert_eq!(received.header("schema_version"), Some(&2.into()));
+
+        let json = serde_json::to_string(&received).unwrap();
+        let restored: DispatchedEvent = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored.headers(), received.headers());
+
+        let without_headers: DispatchedEvent = serde_json::from_str(
+            r#"{"id":"01a152ed-f957-73b2-bc2a-43d4c20ddb1f","created_at":1792392690,"data":"{}","name":"old"}"#,
+        )
+        .unwrap();
+        assert!(without_headers.headers().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_event_ids() {
+        static RECEIVED: Mutex<Option<DispatchedEvent>> = Mutex::new(None);
+        let _handle = UserCreated21::subscribe_fn(|event| {
+            Box::pin(async move {
+                *RECEIVED.lock().unwrap() = Some(event);
+            })
+        })
+        .await;
+
+        let report = event_dispatcher()
+            .dispatch_and_wait(UserCreated21 { id: 1 })
+            .await;
+        let generated = RECEIVED.lock().unwrap().take().unwrap().id();
+        assert_eq!(generated, report.event_id());
+
+        // The ID of an event coming from another system is kept
+        let upstream_id = uuid::Uuid::from_u128(0xABCD);
+        event_dispatcher()
+            .event(UserCreated21 { id: 2 })
+            .id(upstream_id)
+            .dispatch_and_wait()
+            .await;
+        assert_eq!(RECEIVED.lock().unwrap().take().unwrap().id(), upstream_id);
+        assert_ne!(generated, upstream_id);
+    }
+
+    #[tokio::test]
+    async fn test_causation() {
+        static RECEIVED: Mutex<Option<DispatchedEvent>> = Mutex::new(None);
+        let _first = UserCreated12::subscribe_fn(|_| {
+            Box::pin(async move {
+                event_dispatcher()
+                    .dispatch_and_wait(UserCreated13 { id: 1 })
+                    .await;
+            })
+        })
+        .await;
+        let _second = UserCreated13::subscribe_fn(|event| {
+            Box::pin(async move {
+                *RECEIVED.lock().unwrap() = Some(event);
+            })
+        })
+        .await;
+
+        let report = event_dispatcher()
+            .dispatch_and_wait(UserCreated12 { id: 1 })
+            .await;
+        let root = report.event_id();
+
+        let caused = RECEIVED.lock().unwrap().take().unwrap();
+        assert_eq!(caused.causation_id(), Some(root));
+        assert_eq!(caused.correlation_id(), root);
+
+        let chain = event_dispatcher().causal_chain(caused.id());
+        let names: Vec<&str> = chain.iter().map(|link| link.name()).collect();
+        assert_eq!(names, [UserCreated12::event(), UserCreated13::event()]);
+        assert_eq!(chain[0].causation_id(), None);
+        assert_eq!(event_dispatcher().correlated(root).len(), 2);
+
+        // Events dispatched outside of a handler start a new chain
+        let unrelated = DispatchedEvent::new("{}".to_string(), "unrelated".to_string());
+        assert_eq!(unrelated.causation_id(), None);
+        assert_eq!(unrelated.correlation_id(), unrelated.id());
+    }
+
+    #[tokio::test]
+    async fn test_dispatch_cycles() {
+        static RESULT: Mutex<Option<Result<DispatchReport, Error>>> = Mutex::new(None);
+        let _first = UserCreated14::subscribe_fn(|_| {
+            Box::pin(async move {
+                _ = event_dispatcher()
+                    .try_dispatch_and_wait(UserCreated15 { id: 1 })
+                    .await;
+            })
+        })
+        .await;
+        let _second = UserCreated15::subscribe_fn(|_| {
+            Box::pin(async move {
+                let result = event_dispatcher()
+                    .try_dispatch_and_wait(UserCreated14 { id: 1 })
+                    .await;
+                *RESULT.lock().unwrap() = Some(result);
+            })
+        })
+        .await;
+
+        event_dispatcher()
+            .dispatch_sync(UserCreated14 { id: 1 })
+            .await;
+
+        let result = RESULT.lock().unwrap().take().unwrap();
+        assert_eq!(
+            result.unwrap_err(),
+            Error::DispatchCycle {
+                event: UserCreated14::event(),
+                chain: vec![
+                    UserCreated14::event(),
+                    UserCreated15::event(),
+                    UserCreated14::event()
+                ],
+            }
+        );
+
+        let root = DispatchedEvent::new("{}".to_string(), "level.0".to_string());
+        let mut event = root.clone();
+        for level in 1..=3 {
+            let mut next = DispatchedEvent::new("{}".to_string(), format!("level.{}", level));
+            next.set_cause(&event);
+            event = next;
+        }
+        assert_eq!(event.depth(), 3);
+        assert!(check_chain(&event, 3).is_ok());
+        assert!(matches!(
+            check_chain(&event, 2),
+            Err(Error::MaxDepthExceeded { max_depth: 2, .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_event_timestamps() {
+        static RECEIVED: Mutex<Option<DispatchedEvent>> = Mutex::new(None);
+        let _handle = UserCreated16::subscribe_fn(|event| {
+            Box::pin(async move {
+                *RECEIVED.lock().unwrap() = Some(event);
+            })
+        })
+        .await;
+
+        let occurred_at = chrono::Utc::now() - chrono::Duration::milliseconds(1500);
+        event_dispatcher()
+            .event(UserCreated16 { id: 1 })
+            .occurred_at(occurred_at)
+            .dispatch_and_wait()
+            .await;
+
+        let received = RECEIVED.lock().unwrap().take().unwrap();
+        assert_eq!(received.occurred_at(), occurred_at);
+        assert_eq!(received.created_at_ts(), occurred_at.timestamp());
+        let dispatched_at = received.dispatched_at().unwrap();
+        assert!(dispatched_at > occurred_at);
+        assert!(received.handled_at().unwrap() >= dispatched_at);
+
+        let restored: DispatchedEvent =
+            serde_json::from_str(&serde_json::to_string(&received).unwrap()).unwrap();
+        assert_eq!(restored.occurred_at(), occurred_at);
+
+        let old: DispatchedEvent = serde_json::from_str(
+            r#"{"id":"01a152ed-f957-73b2-bc2a-43d4c20ddb1f","created_at":1792392690,"data":"{}","name":"old"}"#,
+        )
+        .unwrap();
+        assert_eq!(old.created_at_ts(), 1792392690);
+        assert_eq!(old.occurred_at().timestamp(), 1792392690);
+        assert!(old.dispatched_at().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_sequence_numbers() {
+        static RECEIVED: Mutex<Vec<u64>> = Mutex::new(Vec::new());
+        let _handle = UserCreated17::subscribe_fn(|event| {
+            Box::pin(async move {
+                RECEIVED.lock().unwrap().push(event.sequence());
+            })
+        })
+        .await;
+
+        for id in 0..3 {
+            event_dispatcher()
+                .dispatch_and_wait(UserCreated17 { id })
+                .await;
+        }
+
+        let received = RECEIVED.lock().unwrap().clone();
+        assert_eq!(received.len(), 3);
+        assert!(received[0] > 0);
+        assert!(received.windows(2).all(|pair| pair[0] < pair[1]));
+    }
+
+    #[tokio::test]
+    async fn test_failing_handlers() {
+        let failing = UserCreated18::subscribe_fn(|_| Box::pin(async { panic!("boom") })).await;
+        let working = UserCreated18::subscribe_fn(|_| Box::pin(async {})).await;
+
+        let report = event_dispatcher()
+            .dispatch_and_wait(UserCreated18 { id: 1 })
+            .await;
+
+        assert_eq!(report.handled_by(), [working.handler_id().clone()]);
+        assert_eq!(report.failed(), [failing.handler_id().clone()]);
+        assert_eq!(panic_message(&"boom"), "boom");
+        assert_eq!(panic_message(&"boom".to_string()), "boom");
+    }
+
+    #[tokio::test]
+    async fn test_redrive_and_pause() {
+        static FAILING: AtomicU64 = AtomicU64::new(0);
+        static WORKING: AtomicU64 = AtomicU64::new(0);
+
+        let failing = UserCreated22::subscribe_fn(|_| {
+            Box::pin(async {
+                if FAILING.fetch_add(1, Ordering::SeqCst) == 0 {
+                    panic!("the first call fails");
+                }
+            })
+        })
+        .await;
+        let _working = UserCreated22::subscribe_with(
+            crate::handler_fn(|_| {
+                Box::pin(async {
+                    WORKING.fetch_add(1, Ordering::SeqCst);
+                })
+            })
+            .with_label("working"),
+        )
+        .await;
+
+        let dispatcher = event_dispatcher();
+        let name = UserCreated22::event();
+        let letter_of = |report: DispatchReport| {
+            dispatcher
+                .dead_letters()
+                .into_iter()
+                .find(|letter| letter.event().id() == report.event_id())
+                .unwrap()
+        };
+        let report = dispatcher.dispatch_and_wait(UserCreated22 { id: 1 }).await;
+        assert_eq!(report.failed(), [failing.handler_id().clone()]);
+        let letter = letter_of(report);
+
+        // The events are not handled in the current thread while the name is paused
+        assert!(dispatcher.pause(&name));
+        assert_eq!(
+            dispatcher
+                .try_dispatch_and_wait(UserCreated22 { id: 2 })
+                .await
+                .unwrap_err(),
+            Error::Paused {
+                event: name.clone()
+            }
+        );
+        assert!(matches!(
+            dispatcher.redrive(letter.id()).await,
+            Err(Error::Paused { .. })
+        ));
+        // The methods that do not return the error count the event as dropped
+        let dropped = || dispatcher.stats().dropped().get(&name).copied();
+        assert_eq!(dropped(), None);
+        dispatcher.dispatch_sync(UserCreated22 { id: 2 }).await;
+        let report = dispatcher.dispatch_and_wait(UserCreated22 { id: 2 }).await;
+        assert!(report.handled_by().is_empty());
+        assert_eq!(dropped(), Some(2));
+        assert_eq!(dispatcher.resume(&name), Some(0));
+
+        // Only the handler that failed is called again, as a retry rather
+        // than a new dispatch
+        let dispatched = dispatcher.stats().dispatched().get(&name).copied();
+        let report = dispatcher.redrive(letter.id()).await.unwrap();
+        let stats = dispatcher.stats();
+        assert_eq!(stats.dispatched().get(&name).copied(), dispatched);
+        assert_eq!(stats.handler(failing.handler_id()).unwrap().retries(), 1);
+        assert_eq!(report.event_id(), letter.event().id());
+        assert_eq!(report.handled_by(), [failing.handler_id().clone()]);
+        assert_eq!(FAILING.load(Ordering::SeqCst), 2);
+        assert_eq!(WORKING.load(Ordering::SeqCst), 1);
+        assert!(dispatcher.dead_letter_by_id(letter.id()).is_none());
+
+        // The dead letter is kept when its handler is gone
+        FAILING.store(0, Ordering::SeqCst);
+        let letter = letter_of(dispatcher.dispatch_and_wait(UserCreated22 { id: 3 }).await);
+        failing.unsubscribe();
+        assert_eq!(
+            dispatcher.redrive(letter.id()).await.unwrap_err(),
+            Error::HandlerNotFound {
+                event: name,
+                handler: letter.handler().clone()
+            }
+        );
+        assert!(dispatcher.dead_letter_by_id(letter.id()).is_some());
+    }
+
+    #[tokio::test]
+    async fn test_dispatcher_stats() {
+        let handle = UserCreated19::subscribe_fn(|_| Box::pin(async {})).await;
+        // Closures defined in the same function share a name, the label sets
+        // the statistics of this one apart
+        let _once = UserCreated19::subscribe_with(
+            crate::handler_fn(|_| Box::pin(async {}))
+                .with_lifetime(Lifetime::Once)
+                .with_label("once"),
+        )
+        .await;
+
+        for id in 0..2 {
+            event_dispatcher()
+                .dispatch_and_wait(UserCreated19 { id })
+                .await;
+        }
+
+        let stats = event_dispatcher().stats();
+        let name = UserCreated19::event();
+        assert_eq!(stats.dispatched().get(&name), Some(&2));
+        assert_eq!(stats.handled().get(&name), Some(&2));
+        assert_eq!(stats.handler(handle.handler_id()).unwrap().calls(), 2);
+        assert!(stats.removed_handlers() >= 1);
+    }
+
+    #[tokio::test]
+    async fn test_registry() {
+        let first = UserCreated20::subscribe_fn(|_| Box::pin(async {})).await;
+        let limited = UserCreated20::subscribe_with(
+            crate::handler_fn(|_| Box::pin(async {}))
+                .with_lifetime(Lifetime::times(3))
+                .with_priority(10),
+        )
+        .await;
+
+        event_dispatcher()
+            .dispatch_and_wait(UserCreated20 { id: 1 })
+            .await;
+
+        let expired =
+            UserCreated20::subscribe_with(crate::handler_fn(|_| Box::pin(async {})).with_lifetime(
+                Lifetime::until(chrono::Utc::now() - chrono::Duration::seconds(1)),
+            ))
+            .await;
+
+        let registry = event_dispatcher().registry();
+        let event = registry.event(&UserCreated20::event()).unwrap();
+        assert!(!event.is_pattern());
+
+        let handlers = event.handlers();
+        assert_eq!(handlers.len(), 3);
+        assert_eq!(handlers[0].handler(), limited.handler_id());
+        assert_eq!(handlers[0].position(), 0);
+        assert_eq!(handlers[0].priority(), 10);
+        assert_eq!(handlers[0].lifetime(), "times");
+        assert_eq!(handlers[0].remaining_calls(), Some(2));
+        assert!(handlers[0].is_enabled());
+        assert!(!handlers[0].has_expired());
+
+        assert_eq!(handlers[1].handler(), first.handler_id());
+        assert_eq!(handlers[1].lifetime(), "forever");
+        assert_eq!(handlers[1].remaining_calls(), None);
+
+        // The snapshot does not remove the expired registration, the next event does
+        assert_eq!(handlers[2].handler(), expired.handler_id());
+        assert!(handlers[2].has_expired());
+        assert_eq!(
+            event_dispatcher().registry().handler_count(),
+            registry.handler_count()
+        );
+        event_dispatcher()
+            .dispatch_and_wait(UserCreated20 { id: 2 })
+            .await;
+
+        limited.unsubscribe();
+        let registry = event_dispatcher().registry();
+        assert_eq!(
+            registry
+                .event(&UserCreated20::event())
+                .unwrap()
+                .handlers()
+                .len(),
+            1
+        );
+    }
+
     #[derive(Clone, serde::Deserialize, serde::Serialize)]
     struct UserCreated {
         id: u32,
@@ -236,6 +1786,153 @@ mod test {
 
     impl Dispatchable for UserCreated2 {}
 
+    #[derive(Clone, serde::Deserialize, serde::Serialize)]
+    struct UserCreated3 {
+        id: u32,
+    }
+
+    impl Dispatchable for UserCreated3 {}
+
+    #[derive(Clone, serde::Deserialize, serde::Serialize)]
+    struct UserCreated4 {
+        id: u32,
+    }
+
+    impl Dispatchable for UserCreated4 {}
+
+    #[derive(Clone, serde::Deserialize, serde::Serialize)]
+    struct UserCreated5 {
+        id: u32,
+    }
+
+    impl Dispatchable for UserCreated5 {}
+
+    #[derive(Clone, serde::Deserialize, serde::Serialize)]
+    struct UserCreated6 {
+        id: u32,
+    }
+
+    impl Dispatchable for UserCreated6 {}
+
+    #[derive(Clone, serde::Deserialize, serde::Serialize)]
+    struct UserCreated7 {
+        id: u32,
+    }
+
+    impl Dispatchable for UserCreated7 {}
+
+    #[derive(Clone, serde::Deserialize, serde::Serialize)]
+    struct UserCreated8 {
+        id: u32,
+    }
+
+    impl Dispatchable for UserCreated8 {}
+
+    #[derive(Clone, serde::Deserialize, serde::Serialize)]
+    struct UserCreated9 {
+        id: u32,
+    }
+
+    impl Dispatchable for UserCreated9 {}
+
+    #[derive(Clone, serde::Deserialize, serde::Serialize)]
+    struct UserCreated10 {
+        id: u32,
+    }
+
+    impl Dispatchable for UserCreated10 {}
+
+    #[derive(Clone, serde::Deserialize, serde::Serialize)]
+    struct UserCreated11 {
+        id: u32,
+    }
+
+    impl Dispatchable for UserCreated11 {}
+
+    #[derive(Clone, serde::Deserialize, serde::Serialize)]
+    struct UserCreated12 {
+        id: u32,
+    }
+
+    impl Dispatchable for UserCreated12 {}
+
+    #[derive(Clone, serde::Deserialize, serde::Serialize)]
+    struct UserCreated13 {
+        id: u32,
+    }
+
+    impl Dispatchable for UserCreated13 {}
+
+    #[derive(Clone, serde::Deserialize, serde::Serialize)]
+    struct UserCreated14 {
+        id: u32,
+    }
+
+    impl Dispatchable for UserCreated14 {}
+
+    #[derive(Clone, serde::Deserialize, serde::Serialize)]
+    struct UserCreated15 {
+        id: u32,
+    }
+
+    impl Dispatchable for UserCreated15 {}
+
+    #[derive(Clone, serde::Deserialize, serde::Serialize)]
+    struct UserCreated16 {
+        id: u32,
+    }
+
+    impl Dispatchable for UserCreated16 {}
+
+    #[derive(Clone, serde::Deserialize, serde::Serialize)]
+    struct UserCreated17 {
+        id: u32,
+    }
+
+    impl Dispatchable for UserCreated17 {}
+
+    #[derive(Clone, serde::Deserialize, serde::Serialize)]
+    struct UserCreated18 {
+        id: u32,
+    }
+
+    impl Dispatchable for UserCreated18 {}
+
+    #[derive(Clone, serde::Deserialize, serde::Serialize)]
+    struct UserCreated19 {
+        id: u32,
+    }
+
+    impl Dispatchable for UserCreated19 {}
+
+    #[derive(Clone, serde::Deserialize, serde::Serialize)]
+    struct UserCreated20 {
+        id: u32,
+    }
+
+    impl Dispatchable for UserCreated20 {}
+
+    #[derive(Clone, serde::Deserialize, serde::Serialize)]
+    struct UserCreated21 {
+        id: u32,
+    }
+
+    impl Dispatchable for UserCreated21 {}
+
+    #[derive(Clone, serde::Deserialize, serde::Serialize)]
+    struct UserCreated22 {
+        id: u32,
+    }
+
+    impl Dispatchable for UserCreated22 {}
+
+    #[derive(Clone, serde::Deserialize, serde::Serialize)]
+    struct UserCreated23 {
+        id: u32,
+    }
+
+    impl Dispatchable for UserCreated23 {}
+
     #[derive(Default)]
     struct HandleUserCreated2;
 
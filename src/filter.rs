@@ -0,0 +1,85 @@
+use crate::{Dispatchable, DispatchedEvent};
+use std::{fmt::Debug, sync::Arc};
+
+/// Decides whether a registration receives an event
+///
+/// Events rejected by the filter are not passed to the handler and do not
+/// count towards the handler's lifetime
+/// ```
+/// # use orsomafo::{Dispatchable, DispatchedEvent, EventHandler, Filter};
+/// # #[tokio::main]
+/// # async fn main() {
+///    #[derive(Clone, serde::Serialize, serde::Deserialize)]
+///    struct OrderPlaced {
+///        tenant_id: String,
+///    }
+///    impl Dispatchable for OrderPlaced {}
+///
+///    let handler = orsomafo::handler_fn(|_| Box::pin(async {}));
+///
+///    // Typed filter on the decoded event
+///    let _handle = OrderPlaced::subscribe_with(
+///        handler.with_filter(Filter::event(|order: &OrderPlaced| order.tenant_id == "acme")),
+///    )
+///    .await;
+///
+///    // JSON pointer filter on the event's data
+///    let _filter = Filter::pointer("/tenant_id", "acme");
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct Filter(Arc<dyn Fn(&DispatchedEvent) -> bool + Send + Sync>);
+
+impl Filter {
+    /// Filters on the dispatched event
+    pub fn new(predicate: impl Fn(&DispatchedEvent) -> bool + Send + Sync + 'static) -> Self {
+        Self(Arc::new(predicate))
+    }
+
+    /// Filters on the decoded event. Events that cannot be decoded are rejected
+    pub fn event<T: Dispatchable>(predicate: impl Fn(&T) -> bool + Send + Sync + 'static) -> Self {
+        Self::new(move |event| {
+            event
+                .the_event::<T>()
+                .map(|the_event| predicate(&the_event))
+                .unwrap_or(false)
+        })
+    }
+
+    /// Accepts events whose data has the expected value at the JSON pointer.
+    /// See `serde_json::Value::pointer`
+    pub fn pointer(pointer: &str, expected: impl Into<serde_json::Value>) -> Self {
+        let expected = expected.into();
+        Self::pointer_matches(pointer, move |value| value == &expected)
+    }
+
+    /// Accepts events whose data has a value at the JSON pointer that satisfies the predicate
+    pub fn pointer_matches(
+        pointer: &str,
+        predicate: impl Fn(&serde_json::Value) -> bool + Send + Sync + 'static,
+    ) -> Self {
+        let pointer = pointer.to_string();
+        Self::new(move |event| {
+            serde_json::from_str::<serde_json::Value>(event.data_ref())
+                .ok()
+                .and_then(|data| data.pointer(&pointer).map(&predicate))
+                .unwrap_or(false)
+        })
+    }
+
+    /// Accepts events accepted by both filters
+    pub fn and(self, other: Filter) -> Self {
+        Self::new(move |event| self.accepts(event) && other.accepts(event))
+    }
+
+    /// Returns true if the event should be passed to the handler
+    pub fn accepts(&self, event: &DispatchedEvent) -> bool {
+        (self.0)(event)
+    }
+}
+
+impl Debug for Filter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Filter")
+    }
+}
@@ -0,0 +1,56 @@
+use std::fmt::Display;
+
+/// Identifies a single registration of a handler
+///
+/// The same handler type can be registered many times. Each registration gets
+/// a unique ID next to the handler's name and the optional label supplied by
+/// `EventHandler::label`
+#[derive(Debug, Clone, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+pub struct HandlerId {
+    registration: u64,
+    name: String,
+    label: Option<String>,
+}
+
+impl HandlerId {
+    pub(crate) fn new(registration: u64, name: String, label: Option<String>) -> Self {
+        Self {
+            registration,
+            name,
+            label,
+        }
+    }
+
+    /// The unique ID of the registration
+    pub fn registration(&self) -> u64 {
+        self.registration
+    }
+
+    /// The handler's name. By default this is the handler's type name
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The label supplied by the user, if any
+    pub fn label(&self) -> Option<&str> {
+        self.label.as_deref()
+    }
+
+    /// The name and the label, without the registration. Used to report the
+    /// statistics of every registration of a handler together
+    pub(crate) fn series(&self) -> String {
+        match &self.label {
+            Some(label) => format!("{}[{}]", self.name, label),
+            None => self.name.clone(),
+        }
+    }
+}
+
+impl Display for HandlerId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.label {
+            Some(label) => write!(f, "{}[{}]#{}", self.name, label, self.registration),
+            None => write!(f, "{}#{}", self.name, self.registration),
+        }
+    }
+}
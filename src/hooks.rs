@@ -0,0 +1,149 @@
+use crate::{
+    dispatched_event::DispatchedEvent,
+    event_listener::{panic_message, LOG_TITLE},
+    handler_id::HandlerId,
+};
+use std::{
+    panic::{catch_unwind, AssertUnwindSafe},
+    sync::Arc,
+    time::Duration,
+};
+
+type EventHook = Arc<dyn Fn(&DispatchedEvent) + Send + Sync>;
+type HandlerHook = Arc<dyn Fn(&DispatchedEvent, &HandlerId) + Send + Sync>;
+type DurationHook = Arc<dyn Fn(&DispatchedEvent, &HandlerId, Duration) + Send + Sync>;
+type ErrorHook = Arc<dyn Fn(&DispatchedEvent, &HandlerId, &str) + Send + Sync>;
+type RemovalHook = Arc<dyn Fn(&HandlerId) + Send + Sync>;
+
+/// The observers registered with `EventDispatcherBuilder`
+#[derive(Default, Clone)]
+pub(crate) struct Hooks {
+    pub(crate) before_dispatch: Vec<EventHook>,
+    pub(crate) before_handle: Vec<HandlerHook>,
+    pub(crate) after_handle: Vec<DurationHook>,
+    pub(crate) on_error: Vec<ErrorHook>,
+    pub(crate) on_removed: Vec<RemovalHook>,
+}
+
+impl std::fmt::Debug for Hooks {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Hooks")
+            .field("before_dispatch", &self.before_dispatch.len())
+            .field("before_handle", &self.before_handle.len())
+            .field("after_handle", &self.after_handle.len())
+            .field("on_error", &self.on_error.len())
+            .field("on_removed", &self.on_removed.len())
+            .finish()
+    }
+}
+
+impl Hooks {
+    pub(crate) fn is_empty(&self) -> bool {
+        self.before_dispatch.is_empty()
+            && self.before_handle.is_empty()
+            && self.after_handle.is_empty()
+            && self.on_error.is_empty()
+            && self.on_removed.is_empty()
+    }
+
+    pub(crate) fn dispatching(&self, event: &DispatchedEvent) {
+        self.before_dispatch
+            .iter()
+            .for_each(|hook| guard("before_dispatch", || hook(event)));
+    }
+
+    pub(crate) fn handling(&self, event: &DispatchedEvent, handler: &HandlerId) {
+        self.before_handle
+            .iter()
+            .for_each(|hook| guard("before_handle", || hook(event, handler)));
+    }
+
+    pub(crate) fn handled(&self, event: &DispatchedEvent, handler: &HandlerId, took: Duration) {
+        self.after_handle
+            .iter()
+            .for_each(|hook| guard("after_handle", || hook(event, handler, took)));
+    }
+
+    pub(crate) fn failed(&self, event: &DispatchedEvent, handler: &HandlerId, error: &str) {
+        self.on_error
+            .iter()
+            .for_each(|hook| guard("on_error", || hook(event, handler, error)));
+    }
+
+    pub(crate) fn removed(&self, handler: &HandlerId) {
+        self.on_removed
+            .iter()
+            .for_each(|hook| guard("on_handler_removed", || hook(handler)));
+    }
+}
+
+// A panicking hook is logged and does not stop the dispatching or the handling
+fn guard(name: &str, hook: impl FnOnce()) {
+    if let Err(panic) = catch_unwind(AssertUnwindSafe(hook)) {
+        log::error!(
+            target: LOG_TITLE,
+            "the {} hook panicked: {}",
+            name,
+            panic_message(panic.as_ref())
+        );
+    }
+}
+
+mod test {
+    #[test]
+    fn test_hooks() {
+        use super::*;
+        use std::sync::Mutex;
+
+        static CALLS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+        let mut hooks = Hooks::default();
+        assert!(hooks.is_empty());
+        hooks.before_dispatch.push(Arc::new(|event| {
+            CALLS
+                .lock()
+                .unwrap()
+                .push(format!("dispatch {}", event.name_ref()))
+        }));
+        hooks.before_handle.push(Arc::new(|_, handler| {
+            CALLS.lock().unwrap().push(format!("handle {}", handler))
+        }));
+        hooks.after_handle.push(Arc::new(|_, handler, took| {
+            CALLS
+                .lock()
+                .unwrap()
+                .push(format!("handled {} in {}ms", handler, took.as_millis()))
+        }));
+        hooks.on_error.push(Arc::new(|_, handler, error| {
+            CALLS
+                .lock()
+                .unwrap()
+                .push(format!("failed {}: {}", handler, error))
+        }));
+        hooks.on_removed.push(Arc::new(|handler| {
+            CALLS.lock().unwrap().push(format!("removed {}", handler))
+        }));
+        hooks
+            .before_handle
+            .insert(0, Arc::new(|_, _| panic!("hook panicked")));
+
+        let event = DispatchedEvent::new("{}".to_string(), "audit".to_string());
+        let handler = HandlerId::new(1, "audit".to_string(), None);
+        hooks.dispatching(&event);
+        hooks.handling(&event, &handler);
+        hooks.handled(&event, &handler, Duration::from_millis(3));
+        hooks.failed(&event, &handler, "boom");
+        hooks.removed(&handler);
+
+        assert_eq!(
+            *CALLS.lock().unwrap(),
+            [
+                "dispatch audit",
+                "handle audit#1",
+                "handled audit#1 in 3ms",
+                "failed audit#1: boom",
+                "removed audit#1"
+            ]
+        );
+    }
+}
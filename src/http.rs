@@ -0,0 +1,320 @@
+use crate::event_listener::LOG_TITLE;
+use std::{future::Future, net::SocketAddr, sync::Arc, time::Duration};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader},
+    net::{TcpListener, TcpStream, ToSocketAddrs},
+    task::JoinHandle,
+};
+
+/// The largest request body the embedded servers accept
+const MAX_BODY: usize = 1024 * 1024;
+
+/// The longest request line or header line the embedded servers accept
+const MAX_LINE: usize = 8 * 1024;
+
+/// The largest number of headers the embedded servers accept
+const MAX_HEADERS: usize = 100;
+
+/// The time a client has to send the whole request
+const READ_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The pause after the listener failed to accept a connection, e.g. when
+/// the process ran out of file descriptors
+const ACCEPT_BACKOFF: Duration = Duration::from_millis(100);
+
+/// A request received by one of the embedded servers
+#[derive(Debug)]
+#[cfg_attr(not(feature = "admin"), allow(dead_code))]
+pub(crate) struct Request {
+    pub(crate) method: String,
+    pub(crate) path: String,
+    /// The header names are lowercase
+    pub(crate) headers: Vec<(String, String)>,
+    pub(crate) body: String,
+}
+
+impl Request {
+    pub(crate) fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+/// A response of one of the embedded servers
+#[derive(Debug)]
+pub(crate) struct Response {
+    pub(crate) status: u16,
+    pub(crate) content_type: &'static str,
+    pub(crate) body: String,
+}
+
+impl Response {
+    pub(crate) fn new(status: u16, content_type: &'static str, body: String) -> Self {
+        Self {
+            status,
+            content_type,
+            body,
+        }
+    }
+
+    pub(crate) fn text(status: u16, body: &str) -> Self {
+        Self::new(status, "text/plain; charset=utf-8", body.to_string())
+    }
+
+    #[cfg_attr(not(feature = "admin"), allow(dead_code))]
+    pub(crate) fn json(status: u16, body: &impl serde::Serialize) -> Self {
+        match serde_json::to_string(body) {
+            Ok(body) => Self::new(status, "application/json", body),
+            Err(error) => Self::text(500, &error.to_string()),
+        }
+    }
+
+    fn reason(&self) -> &'static str {
+        match self.status {
+            200 => "OK",
+            202 => "Accepted",
+            400 => "Bad Request",
+            401 => "Unauthorized",
+            403 => "Forbidden",
+            404 => "Not Found",
+            405 => "Method Not Allowed",
+            408 => "Request Timeout",
+            409 => "Conflict",
+            413 => "Payload Too Large",
+            422 => "Unprocessable Entity",
+            431 => "Request Header Fields Too Large",
+            _ => "Internal Server Error",
+        }
+    }
+}
+
+/// A running embedded server. The server stops when the handle is dropped
+#[derive(Debug)]
+pub struct ServerHandle {
+    local_addr: SocketAddr,
+    task: JoinHandle<()>,
+}
+
+impl ServerHandle {
+    /// The address the server is listening on
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+
+    /// Stops the server
+    pub fn shutdown(self) {}
+}
+
+impl Drop for ServerHandle {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+/// Binds the address and answers every connection with the handler
+///
+/// The check runs once the headers are read and before the body is: a response
+/// returned by it is sent without reading the body
+pub(crate) async fn serve<A, C, H, F>(
+    addr: A,
+    check: C,
+    handler: H,
+) -> std::io::Result<ServerHandle>
+where
+    A: ToSocketAddrs,
+    C: Fn(&Request) -> Option<Response> + Send + Sync + 'static,
+    H: Fn(Request) -> F + Send + Sync + 'static,
+    F: Future<Output = Response> + Send + 'static,
+{
+    let listener = TcpListener::bind(addr).await?;
+    let local_addr = listener.local_addr()?;
+    let check = Arc::new(check);
+    let handler = Arc::new(handler);
+
+    let task = tokio::spawn(async move {
+        loop {
+            let stream = match listener.accept().await {
+                Ok((stream, _)) => stream,
+                Err(error) => {
+                    log::warn!(target: LOG_TITLE, "could not accept connection: {}", error);
+                    tokio::time::sleep(ACCEPT_BACKOFF).await;
+                    continue;
+                }
+            };
+            let check = check.clone();
+            let handler = handler.clone();
+            tokio::spawn(async move {
+                if let Err(error) = answer(stream, check.as_ref(), handler.as_ref()).await {
+                    log::debug!(target: LOG_TITLE, "could not answer request: {}", error);
+                }
+            });
+        }
+    });
+
+    Ok(ServerHandle { local_addr, task })
+}
+
+async fn answer<C, H, F>(stream: TcpStream, check: &C, handler: &H) -> std::io::Result<()>
+where
+    C: Fn(&Request) -> Option<Response>,
+    H: Fn(Request) -> F,
+    F: Future<Output = Response>,
+{
+    let mut reader = BufReader::new(stream);
+    let read = tokio::time::timeout(READ_TIMEOUT, read_request(&mut reader, check)).await;
+    let response = match read {
+        Ok(read) => match read? {
+            Ok(request) => handler(request).await,
+            Err(response) => response,
+        },
+        Err(_) => Response::text(408, "the request took too long"),
+    };
+
+    let mut stream = reader.into_inner();
+    let head = format!(
+        "HTTP/1.1 {} {}\r\ncontent-type: {}\r\ncontent-length: {}\r\nconnection: close\r\n\r\n",
+        response.status,
+        response.reason(),
+        response.content_type,
+        response.body.len()
+    );
+    stream.write_all(head.as_bytes()).await?;
+    stream.write_all(response.body.as_bytes()).await?;
+    stream.shutdown().await
+}
+
+async fn read_request<C>(
+    reader: &mut BufReader<TcpStream>,
+    check: &C,
+) -> std::io::Result<Result<Request, Response>>
+where
+    C: Fn(&Request) -> Option<Response>,
+{
+    let mut line = String::new();
+    if !read_line(reader, &mut line).await? {
+        return Ok(Err(Response::text(431, "the request line is too long")));
+    }
+    let mut parts = line.split_whitespace();
+    let (Some(method), Some(path)) = (parts.next(), parts.next()) else {
+        return Ok(Err(Response::text(400, "malformed request")));
+    };
+    let (method, path) = (method.to_string(), path.to_string());
+
+    let mut headers = Vec::new();
+    loop {
+        line.clear();
+        if !read_line(reader, &mut line).await? {
+            return Ok(Err(Response::text(431, "a header is too long")));
+        }
+        if line.trim().is_empty() {
+            break;
+        }
+        if headers.len() == MAX_HEADERS {
+            return Ok(Err(Response::text(431, "too many headers")));
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            headers.push((name.trim().to_lowercase(), value.trim().to_string()));
+        }
+    }
+
+    let mut request = Request {
+        method,
+        path,
+        headers,
+        body: String::new(),
+    };
+    if let Some(response) = check(&request) {
+        return Ok(Err(response));
+    }
+
+    let length = request
+        .header("content-length")
+        .map_or(Some(0), |value| value.parse::<usize>().ok());
+    let Some(length) = length else {
+        return Ok(Err(Response::text(400, "invalid content-length")));
+    };
+    if length > MAX_BODY {
+        return Ok(Err(Response::text(413, "the body is too large")));
+    }
+
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body).await?;
+    let Ok(body) = String::from_utf8(body) else {
+        return Ok(Err(Response::text(400, "the body is not UTF-8")));
+    };
+    request.body = body;
+
+    Ok(Ok(request))
+}
+
+/// Reads a line of at most `MAX_LINE` bytes. Returns false if the line is longer
+async fn read_line(reader: &mut BufReader<TcpStream>, line: &mut String) -> std::io::Result<bool> {
+    let read = reader.take(MAX_LINE as u64).read_line(line).await?;
+    Ok(read < MAX_LINE || line.ends_with('\n'))
+}
+
+mod test {
+    #[tokio::test(start_paused = true)]
+    async fn test_request_limits() {
+        use super::*;
+
+        async fn send(server: &ServerHandle, request: &[u8], finish: bool) -> String {
+            let mut stream = TcpStream::connect(server.local_addr()).await.unwrap();
+            stream.write_all(request).await.unwrap();
+            if finish {
+                stream.shutdown().await.unwrap();
+            }
+            let mut response = String::new();
+            stream.read_to_string(&mut response).await.unwrap();
+            response
+        }
+
+        let server = serve(
+            "127.0.0.1:0",
+            |request| {
+                (request.header("authorization") != Some("yes"))
+                    .then(|| Response::text(401, "unauthorized"))
+            },
+            |request| async move { Response::text(200, &request.body) },
+        )
+        .await
+        .unwrap();
+
+        let response = send(
+            &server,
+            b"POST / HTTP/1.1\r\nauthorization: yes\r\ncontent-length: 2\r\n\r\nok",
+            false,
+        )
+        .await;
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(response.ends_with("\r\n\r\nok"));
+
+        // The check runs before the body is sent
+        let response = send(
+            &server,
+            b"POST / HTTP/1.1\r\ncontent-length: 2\r\n\r\n",
+            false,
+        )
+        .await;
+        assert!(response.starts_with("HTTP/1.1 401 Unauthorized\r\n"));
+
+        let long_path = format!("GET /{} HTTP/1.1\r\n\r\n", "a".repeat(MAX_LINE));
+        let response = send(&server, long_path.as_bytes(), true).await;
+        assert!(response.starts_with("HTTP/1.1 431 "));
+
+        let many_headers = format!(
+            "GET / HTTP/1.1\r\n{}\r\n",
+            "x: y\r\n".repeat(MAX_HEADERS + 1)
+        );
+        let response = send(&server, many_headers.as_bytes(), true).await;
+        assert!(response.starts_with("HTTP/1.1 431 "));
+
+        // The client never finishes the headers
+        let response = send(&server, b"GET / HTTP/1.1\r\nauthorization: yes\r\n", false).await;
+        assert!(response.starts_with("HTTP/1.1 408 Request Timeout\r\n"));
+
+        server.shutdown();
+    }
+}
@@ -0,0 +1,141 @@
+use crate::{clock, event_dispatcher::EVENT_DISPATCHER};
+use std::sync::{Mutex, PoisonError};
+use uuid::Uuid;
+
+/// Creates the IDs of the dispatched events
+///
+/// Set with `EventDispatcherBuilder::id_generator`. Closures returning a `Uuid`
+/// can be used as generators
+/// ```
+/// # use orsomafo::{EventDispatcherBuilder, SnowflakeGenerator};
+/// # #[tokio::main]
+/// # async fn main() {
+///    EventDispatcherBuilder::new()
+///        .id_generator(SnowflakeGenerator::new(7))
+///        .build()
+///        .await;
+/// # }
+/// ```
+pub trait IdGenerator: Send + Sync + 'static {
+    /// The ID of the next event
+    fn generate(&self) -> Uuid;
+}
+
+impl<F: Fn() -> Uuid + Send + Sync + 'static> IdGenerator for F {
+    fn generate(&self) -> Uuid {
+        self()
+    }
+}
+
+/// Creates time ordered version 7 UUIDs. This is the default generator
+#[derive(Debug, Clone, Copy, Default)]
+pub struct UuidV7Generator;
+
+impl IdGenerator for UuidV7Generator {
+    fn generate(&self) -> Uuid {
+        Uuid::now_v7()
+    }
+}
+
+/// Creates ULIDs: 48 bits of milliseconds since the epoch followed by 80 random bits.
+/// The ULID is stored as the 128 bits of the UUID
+#[derive(Debug, Clone, Copy, Default)]
+pub struct UlidGenerator;
+
+impl IdGenerator for UlidGenerator {
+    fn generate(&self) -> Uuid {
+        let millis = clock::now().timestamp_millis() as u128 & 0xFFFF_FFFF_FFFF;
+        let random = Uuid::new_v4().as_u128() & ((1 << 80) - 1);
+        Uuid::from_u128(millis << 80 | random)
+    }
+}
+
+/// Creates snowflake IDs: 41 bits of milliseconds since the epoch of the generator,
+/// 10 bits of node ID and 12 bits of sequence. The snowflake is stored in the
+/// lower 64 bits of the UUID
+#[derive(Debug)]
+pub struct SnowflakeGenerator {
+    node_id: u16,
+    epoch_millis: i64,
+    // The milliseconds and sequence of the last ID
+    last: Mutex<(i64, u16)>,
+}
+
+impl SnowflakeGenerator {
+    /// 2024-01-01T00:00:00Z
+    pub const DEFAULT_EPOCH_MILLIS: i64 = 1_704_067_200_000;
+    const MAX_NODE_ID: u16 = (1 << 10) - 1;
+    const MAX_SEQUENCE: u16 = (1 << 12) - 1;
+
+    /// Creates a generator for the node. Only the lower 10 bits of the node ID are used
+    pub fn new(node_id: u16) -> Self {
+        Self::with_epoch(node_id, Self::DEFAULT_EPOCH_MILLIS)
+    }
+
+    /// Creates a generator that counts the milliseconds from the given epoch
+    pub fn with_epoch(node_id: u16, epoch_millis: i64) -> Self {
+        Self {
+            node_id: node_id & Self::MAX_NODE_ID,
+            epoch_millis,
+            last: Mutex::new((i64::MIN, 0)),
+        }
+    }
+
+    /// The node ID of a snowflake created by this generator
+    pub fn node_id_of(id: &Uuid) -> u16 {
+        ((id.as_u64_pair().1 >> 12) as u16) & Self::MAX_NODE_ID
+    }
+}
+
+impl IdGenerator for SnowflakeGenerator {
+    fn generate(&self) -> Uuid {
+        let now = (clock::now().timestamp_millis() - self.epoch_millis).max(0);
+        let mut last = self.last.lock().unwrap_or_else(PoisonError::into_inner);
+
+        // IDs never go backwards. When the clock goes back or the sequence of the
+        // millisecond is used up, the IDs are borrowed from the next millisecond
+        *last = if now > last.0 {
+            (now, 0)
+        } else if last.1 < Self::MAX_SEQUENCE {
+            (last.0, last.1 + 1)
+        } else {
+            (last.0 + 1, 0)
+        };
+
+        let (millis, sequence) = *last;
+        let snowflake = ((millis as u64) & ((1 << 41) - 1)) << 22
+            | (self.node_id as u64) << 12
+            | sequence as u64;
+        Uuid::from_u64_pair(0, snowflake)
+    }
+}
+
+/// The next ID according to the generator of the dispatcher
+pub(crate) fn next_id() -> Uuid {
+    match EVENT_DISPATCHER.get() {
+        Some(dispatcher) => dispatcher.id_generator().generate(),
+        None => Uuid::now_v7(),
+    }
+}
+
+mod test {
+    #[test]
+    fn test_id_generators() {
+        use super::*;
+
+        let generator = SnowflakeGenerator::new(42);
+        let ids: Vec<Uuid> = (0..5000).map(|_| generator.generate()).collect();
+        assert!(ids.windows(2).all(|pair| pair[0] < pair[1]));
+        assert!(ids
+            .iter()
+            .all(|id| SnowflakeGenerator::node_id_of(id) == 42));
+
+        let ulid = UlidGenerator.generate();
+        let millis = (ulid.as_u128() >> 80) as i64;
+        assert!((chrono::Utc::now().timestamp_millis() - millis).abs() < 10_000);
+        assert_ne!(ulid, UlidGenerator.generate());
+
+        let fixed = Uuid::from_u128(7);
+        assert_eq!((move || fixed).generate(), fixed);
+    }
+}
@@ -33,7 +33,8 @@
 //!
 //!  #[tokio::main]
 //!  async fn main() {
-//!    MyEvent::subscribe::<MyEventHandler>().await;
+//!    // Keep the handle around for as long as the handler should be called
+//!    let _handle = MyEvent::subscribe::<MyEventHandler>().await;
 //!
 //!    let event = MyEvent;
 //!    event.dispatch_event();
@@ -89,25 +90,90 @@
 //!   sleep(Duration::from_millis(100)).await;
 //! }
 //! ```
+#[cfg(feature = "admin")]
+mod admin;
 mod builder;
+mod causation;
+mod clock;
 mod closure_handler_wrapper;
+mod configured_handler;
+mod dead_letter;
+mod dispatch_builder;
+mod dispatch_report;
 mod dispatched_event;
+mod error;
 mod event;
 mod event_dispatcher;
 mod event_listener;
+mod filter;
+mod handler_id;
+mod hooks;
+#[cfg(any(feature = "prometheus", feature = "admin"))]
+mod http;
+mod id_generator;
+mod lifetime;
+mod middleware;
+mod namespace;
+mod ordering;
+mod pattern;
+mod pause;
+#[cfg(feature = "prometheus")]
+mod prometheus;
+mod propagation;
+mod registry;
+mod sequence;
+mod stats;
+mod subscriber_group;
+mod subscription_handle;
+mod topic;
+#[cfg(feature = "tower")]
+mod tower_service;
+mod trace_spans;
+mod unhandled;
 
 pub use async_trait::async_trait;
 pub use serde;
 
+#[cfg(feature = "admin")]
+pub use admin::AdminServer;
 pub use builder::EventDispatcherBuilder;
+pub use causation::CausalLink;
+pub use clock::{Clock, ManualClock, SystemClock};
+pub use closure_handler_wrapper::handler_fn;
+pub use configured_handler::ConfiguredHandler;
+pub use dead_letter::DeadLetter;
+pub use dispatch_builder::DispatchBuilder;
+pub use dispatch_report::DispatchReport;
 pub use dispatched_event::DispatchedEvent;
+pub use error::Error;
 pub use event::*;
 pub use event_dispatcher::event_dispatcher;
 pub use event_dispatcher::EventDispatcher;
 pub use event_listener::Subscriber;
+pub use filter::Filter;
+pub use handler_id::HandlerId;
+#[cfg(any(feature = "prometheus", feature = "admin"))]
+pub use http::ServerHandle;
+pub use id_generator::{IdGenerator, SnowflakeGenerator, UlidGenerator, UuidV7Generator};
+pub use lifetime::Lifetime;
+pub use middleware::{Middleware, Next};
+pub use namespace::NamespaceSettings;
+#[cfg(feature = "prometheus")]
+pub use prometheus::PrometheusExporter;
+pub use propagation::{TRACEPARENT_HEADER, TRACESTATE_HEADER};
+pub use registry::{RegisteredEvent, RegisteredHandler, Registry};
+pub use sequence::{SequenceStatus, SequenceTracker};
+pub use stats::{HandlerStats, Stats, OTHER_LABEL};
+pub use subscriber_group::SubscriberGroup;
+pub use subscription_handle::SubscriptionHandle;
+pub use topic::Topic;
+#[cfg(feature = "tower")]
+pub use tower_service::{service_handler, HandlerService, ServiceHandler};
+pub use unhandled::UnhandledPolicy;
 
 ///! A simple way to setup the dispatcher
 ///!
+#[allow(clippy::suspicious_doc_comments)]
 pub async fn setup() {
     EventDispatcherBuilder::new().build().await;
 }
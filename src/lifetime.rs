@@ -0,0 +1,110 @@
+use crate::{clock, DispatchedEvent};
+use chrono::{DateTime, Utc};
+use std::{fmt::Debug, sync::Arc, time::Duration};
+
+/// How long a registration stays registered
+///
+/// A registration that reaches the end of its lifetime is removed
+#[derive(Clone, Default)]
+pub enum Lifetime {
+    /// Stays registered until it is unsubscribed
+    #[default]
+    Forever,
+    /// Removed after handling one event
+    Once,
+    /// Removed after handling the given number of events. `Times(0)` handles no event
+    Times(u32),
+    /// Removed once the deadline passes. Events that arrive after the deadline are not handled.
+    /// The deadline is compared with the clock of the dispatcher, see `EventDispatcherBuilder::clock`
+    Until(DateTime<Utc>),
+    /// Removed as soon as the predicate returns true for an event.
+    /// That event is not handled
+    UntilEvent(Arc<dyn Fn(&DispatchedEvent) -> bool + Send + Sync>),
+}
+
+impl Lifetime {
+    /// Removed after handling the given number of events
+    pub fn times(total: u32) -> Self {
+        Self::Times(total)
+    }
+
+    /// Removed once the deadline passes
+    pub fn until(deadline: DateTime<Utc>) -> Self {
+        Self::Until(deadline)
+    }
+
+    /// Removed once the duration has elapsed, starting now
+    pub fn expire_after(duration: Duration) -> Self {
+        let duration = chrono::Duration::from_std(duration).unwrap_or(chrono::Duration::MAX);
+        Self::Until(
+            clock::now()
+                .checked_add_signed(duration)
+                .unwrap_or(DateTime::<Utc>::MAX_UTC),
+        )
+    }
+
+    /// Removed as soon as the predicate returns true for an event
+    pub fn until_event(
+        predicate: impl Fn(&DispatchedEvent) -> bool + Send + Sync + 'static,
+    ) -> Self {
+        Self::UntilEvent(Arc::new(predicate))
+    }
+
+    /// The maximum number of events that can be handled, if limited
+    pub(crate) fn max_calls(&self) -> Option<u32> {
+        match self {
+            Self::Once => Some(1),
+            Self::Times(total) => Some(*total),
+            _ => None,
+        }
+    }
+
+    /// The name of the variant
+    pub(crate) fn kind(&self) -> &'static str {
+        match self {
+            Self::Forever => "forever",
+            Self::Once => "once",
+            Self::Times(_) => "times",
+            Self::Until(_) => "until",
+            Self::UntilEvent(_) => "until_event",
+        }
+    }
+
+    /// The time left before the deadline, if there is one
+    pub(crate) fn expires_in(&self) -> Option<Duration> {
+        match self {
+            Self::Until(deadline) => Some((*deadline - clock::now()).to_std().unwrap_or_default()),
+            _ => None,
+        }
+    }
+
+    /// Returns true if the lifetime ended before the event could be handled
+    pub(crate) fn has_ended(&self, event: &DispatchedEvent) -> bool {
+        match self {
+            Self::Times(0) => true,
+            Self::Until(deadline) => clock::now() >= *deadline,
+            Self::UntilEvent(predicate) => predicate(event),
+            _ => false,
+        }
+    }
+
+    /// Returns true if the lifetime ended regardless of the next event
+    pub(crate) fn has_expired(&self, calls: u32) -> bool {
+        match self {
+            Self::Until(deadline) => clock::now() >= *deadline,
+            _ => self.max_calls().is_some_and(|max| calls >= max),
+        }
+    }
+}
+
+impl Debug for Lifetime {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Forever => write!(f, "Forever"),
+            Self::Once => write!(f, "Once"),
+            Self::Times(total) => f.debug_tuple("Times").field(total).finish(),
+            Self::Until(deadline) => f.debug_tuple("Until").field(deadline).finish(),
+            Self::UntilEvent(_) => write!(f, "UntilEvent"),
+        }
+    }
+}
@@ -0,0 +1,218 @@
+use crate::{
+    dispatched_event::DispatchedEvent,
+    error::Error,
+    event::{EventHandler, Outcome},
+    event_listener::LOG_TITLE,
+    handler_id::HandlerId,
+};
+use async_trait::async_trait;
+use futures::future::BoxFuture;
+use std::sync::Arc;
+
+/// Behavior plugged around the dispatching and the handling of every event
+///
+/// Middlewares are added with `EventDispatcherBuilder::middleware` and are
+/// called in the order they were added
+/// ```
+/// # use orsomafo::{DispatchedEvent, EventDispatcherBuilder, HandlerId, Middleware, Next, Outcome};
+/// struct Tenant;
+///
+/// #[orsomafo::async_trait]
+/// impl Middleware for Tenant {
+///     fn on_dispatch(&self, event: &mut DispatchedEvent) -> Result<(), String> {
+///         if event.name_ref().starts_with("admin.") {
+///             return Err("admin events cannot be dispatched".to_string());
+///         }
+///         event.insert_header("tenant", "acme");
+///         Ok(())
+///     }
+///
+///     async fn on_handle(&self, event: DispatchedEvent, handler: &HandlerId, next: Next<'_>) -> Outcome {
+///         log::info!("{} is handling {}", handler, event.name_ref());
+///         next.run(event).await
+///     }
+/// }
+///
+/// # #[tokio::main]
+/// # async fn main() {
+///    EventDispatcherBuilder::new().middleware(Tenant).build().await;
+/// # }
+/// ```
+#[async_trait]
+pub trait Middleware: Send + Sync + 'static {
+    /// Called when the event is dispatched, before it is queued. The event can be
+    /// changed. Returning an error refuses the event with `Error::Rejected`
+    fn on_dispatch(&self, _event: &mut DispatchedEvent) -> Result<(), String> {
+        Ok(())
+    }
+
+    /// Called around each call of a handler. The handler is only called when
+    /// `next` is run. Return an `Outcome` without running `next` to skip the handler.
+    /// A skipped handler does not use up a call of its lifetime and is listed in
+    /// `DispatchReport::skipped` instead of `handled_by`. Skipping with `Outcome::Stop`
+    /// also skips the remaining handlers, see `DispatchReport::stopped_before`
+    async fn on_handle(
+        &self,
+        event: DispatchedEvent,
+        _handler: &HandlerId,
+        next: Next<'_>,
+    ) -> Outcome {
+        next.run(event).await
+    }
+}
+
+/// The end of the middleware chain
+pub(crate) trait Terminal: Send + Sync {
+    /// Calls the handler
+    fn reach(&self, event: DispatchedEvent) -> BoxFuture<'_, Outcome>;
+}
+
+impl<H: EventHandler + ?Sized> Terminal for H {
+    fn reach(&self, event: DispatchedEvent) -> BoxFuture<'_, Outcome> {
+        self.handle_event(event)
+    }
+}
+
+/// The rest of the middleware chain, ending with the handler
+pub struct Next<'a> {
+    middlewares: &'a [Arc<dyn Middleware>],
+    handler_id: &'a HandlerId,
+    handler: &'a dyn Terminal,
+}
+
+impl<'a> Next<'a> {
+    pub(crate) fn new(
+        middlewares: &'a [Arc<dyn Middleware>],
+        handler_id: &'a HandlerId,
+        handler: &'a dyn Terminal,
+    ) -> Self {
+        Self {
+            middlewares,
+            handler_id,
+            handler,
+        }
+    }
+
+    /// Runs the next middleware, or the handler when there are none left
+    pub async fn run(self, event: DispatchedEvent) -> Outcome {
+        match self.middlewares.split_first() {
+            Some((middleware, rest)) => {
+                let handler_id = self.handler_id;
+                let next = Self::new(rest, handler_id, self.handler);
+                middleware.on_handle(event, handler_id, next).await
+            }
+            None => self.handler.reach(event).await,
+        }
+    }
+}
+
+/// Passes the event through the dispatch hook of the middlewares
+pub(crate) fn on_dispatch(
+    middlewares: &[Arc<dyn Middleware>],
+    event: &mut DispatchedEvent,
+) -> Result<(), Error> {
+    for middleware in middlewares {
+        if let Err(reason) = middleware.on_dispatch(event) {
+            log::warn!(
+                target: LOG_TITLE,
+                "event: {:?} was rejected: {}",
+                event.name_ref(),
+                &reason
+            );
+            return Err(Error::Rejected {
+                event: event.name(),
+                reason,
+            });
+        }
+    }
+
+    Ok(())
+}
+
+mod test {
+    #[tokio::test]
+    async fn test_middleware_chain() {
+        use super::*;
+        use crate::handler_fn;
+        use std::sync::Mutex;
+
+        static CALLS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+        struct Record(&'static str);
+
+        #[async_trait]
+        impl Middleware for Record {
+            fn on_dispatch(&self, event: &mut DispatchedEvent) -> Result<(), String> {
+                if event.name_ref() == "blocked" {
+                    return Err(format!("blocked by {}", self.0));
+                }
+                event.insert_header(self.0, true);
+                Ok(())
+            }
+
+            async fn on_handle(
+                &self,
+                event: DispatchedEvent,
+                _handler: &HandlerId,
+                next: Next<'_>,
+            ) -> Outcome {
+                if event.header("skip").is_some() && self.0 == "inner" {
+                    return Outcome::Stop;
+                }
+                CALLS.lock().unwrap().push(format!("{} before", self.0));
+                let outcome = next.run(event).await;
+                CALLS.lock().unwrap().push(format!("{} after", self.0));
+                outcome
+            }
+        }
+
+        let middlewares: Vec<Arc<dyn Middleware>> =
+            vec![Arc::new(Record("outer")), Arc::new(Record("inner"))];
+
+        let mut event = DispatchedEvent::new("{}".to_string(), "middleware".to_string());
+        assert!(on_dispatch(&middlewares, &mut event).is_ok());
+        assert_eq!(event.header("outer"), Some(&true.into()));
+        assert_eq!(event.header("inner"), Some(&true.into()));
+
+        let mut blocked = DispatchedEvent::new("{}".to_string(), "blocked".to_string());
+        assert_eq!(
+            on_dispatch(&middlewares, &mut blocked),
+            Err(Error::Rejected {
+                event: "blocked".to_string(),
+                reason: "blocked by outer".to_string()
+            })
+        );
+
+        let handler = handler_fn(|_| {
+            Box::pin(async {
+                CALLS.lock().unwrap().push("handler".to_string());
+            })
+        });
+        let handler_id = HandlerId::new(0, "handler".to_string(), None);
+
+        let outcome = Next::new(&middlewares, &handler_id, &handler)
+            .run(event.clone())
+            .await;
+        assert_eq!(outcome, Outcome::Continue);
+        assert_eq!(
+            CALLS.lock().unwrap().drain(..).collect::<Vec<String>>(),
+            [
+                "outer before",
+                "inner before",
+                "handler",
+                "inner after",
+                "outer after"
+            ]
+        );
+
+        event.insert_header("skip", true);
+        let outcome = Next::new(&middlewares, &handler_id, &handler)
+            .run(event)
+            .await;
+        assert_eq!(outcome, Outcome::Stop);
+        assert_eq!(
+            CALLS.lock().unwrap().drain(..).collect::<Vec<String>>(),
+            ["outer before", "outer after"]
+        );
+    }
+}
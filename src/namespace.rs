@@ -0,0 +1,238 @@
+use crate::{
+    dispatched_event::DispatchedEvent,
+    event_listener::{handle_queued, Queued, LOG_TITLE},
+    stats,
+    topic::Topic,
+    trace_spans::DispatchSpan,
+};
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex, PoisonError},
+};
+use tokio::sync::{mpsc, Semaphore};
+
+/// Settings for the events dispatched under a topic
+///
+/// ```
+/// # use orsomafo::{EventDispatcherBuilder, NamespaceSettings};
+/// # #[tokio::main]
+/// # async fn main() {
+///    EventDispatcherBuilder::new()
+///        .namespace(
+///            "billing",
+///            NamespaceSettings::new()
+///                .capacity(1_000) // Events are dropped when 1000 events are waiting
+///                .concurrency(4) // Handle up to 4 events at the same time
+///                .retention(50), // Keep the last 50 events
+///        )
+///        .build()
+///        .await;
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct NamespaceSettings {
+    capacity: Option<usize>,
+    concurrency: usize,
+    retention: usize,
+}
+
+impl Default for NamespaceSettings {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl NamespaceSettings {
+    /// Unbounded queue, one event at a time and no retention
+    pub fn new() -> Self {
+        Self {
+            capacity: None,
+            concurrency: 1,
+            retention: 0,
+        }
+    }
+
+    /// The maximum number of events waiting to be handled.
+    /// Events dispatched while the queue is full are dropped
+    pub fn capacity(mut self, capacity: usize) -> Self {
+        self.capacity = Some(capacity.max(1));
+        self
+    }
+
+    /// The number of events that are handled at the same time.
+    /// Events are handled in the order they are dispatched when this is `1`
+    pub fn concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    /// The number of recently dispatched events to keep
+    pub fn retention(mut self, retention: usize) -> Self {
+        self.retention = retention;
+        self
+    }
+}
+
+#[derive(Debug)]
+enum NamespaceSender {
+    Bounded(mpsc::Sender<Queued>),
+    Unbounded(mpsc::UnboundedSender<Queued>),
+}
+
+/// A queue with its own worker for the events dispatched under a topic
+#[derive(Debug)]
+pub(crate) struct Namespace {
+    topic: Topic,
+    settings: NamespaceSettings,
+    sender: NamespaceSender,
+    retained: Mutex<VecDeque<DispatchedEvent>>,
+}
+
+impl Namespace {
+    /// Creates the namespace and spawns its worker
+    pub(crate) fn start(topic: Topic, settings: NamespaceSettings) -> Self {
+        let permits = Arc::new(Semaphore::new(settings.concurrency));
+        let sender = match settings.capacity {
+            Some(capacity) => {
+                let (tx, mut rx) = mpsc::channel::<Queued>(capacity);
+                tokio::spawn(async move {
+                    while let Some(event) = rx.recv().await {
+                        Self::handle(event, permits.clone()).await;
+                    }
+                });
+                NamespaceSender::Bounded(tx)
+            }
+            None => {
+                let (tx, mut rx) = mpsc::unbounded_channel::<Queued>();
+                tokio::spawn(async move {
+                    while let Some(event) = rx.recv().await {
+                        Self::handle(event, permits.clone()).await;
+                    }
+                });
+                NamespaceSender::Unbounded(tx)
+            }
+        };
+
+        Self {
+            topic,
+            settings,
+            sender,
+            retained: Mutex::new(VecDeque::new()),
+        }
+    }
+
+    async fn handle((event, dispatch): Queued, permits: Arc<Semaphore>) {
+        let permit = permits
+            .acquire_owned()
+            .await
+            .expect("the namespace semaphore was closed");
+        stats::record(|metrics| metrics.dequeued());
+        tokio::spawn(async move {
+            handle_queued(event, dispatch).await;
+            drop(permit);
+        });
+    }
+
+    pub(crate) fn topic(&self) -> &Topic {
+        &self.topic
+    }
+
+    /// Queues the event. Returns false if the event was dropped.
+    /// Only the queued events are retained
+    pub(crate) fn send(&self, event: DispatchedEvent, dispatch: DispatchSpan) -> bool {
+        let retained = (self.settings.retention > 0).then(|| event.clone());
+        let sent = match &self.sender {
+            NamespaceSender::Bounded(sender) => sender.try_send((event, dispatch)).is_ok(),
+            NamespaceSender::Unbounded(sender) => sender.send((event, dispatch)).is_ok(),
+        };
+
+        if sent {
+            stats::record(|metrics| metrics.queued());
+            if let Some(event) = retained {
+                self.retain(event);
+            }
+        } else {
+            log::warn!(
+                target: LOG_TITLE,
+                "dropped event for namespace: {}, the queue is full or closed",
+                &self.topic
+            );
+        }
+
+        sent
+    }
+
+    fn retain(&self, event: DispatchedEvent) {
+        let mut retained = self.retained.lock().unwrap_or_else(PoisonError::into_inner);
+        if retained.len() == self.settings.retention {
+            retained.pop_front();
+        }
+        retained.push_back(event);
+    }
+
+    /// The recently dispatched events, oldest first
+    pub(crate) fn retained(&self) -> Vec<DispatchedEvent> {
+        self.retained
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .iter()
+            .cloned()
+            .collect()
+    }
+}
+
+mod test {
+    #[tokio::test]
+    async fn test_namespace_retention() {
+        use super::*;
+
+        let namespace = Namespace::start(
+            Topic::parse("retention_test"),
+            NamespaceSettings::new().retention(2),
+        );
+
+        for name in ["retention_test/a", "retention_test/b", "retention_test/c"] {
+            let event = DispatchedEvent::new("{}".to_string(), name.to_string());
+            let dispatch = DispatchSpan::open(&event);
+            assert!(namespace.send(event, dispatch));
+        }
+
+        let retained = namespace
+            .retained()
+            .iter()
+            .map(|e| e.name())
+            .collect::<Vec<String>>();
+        assert_eq!(retained, vec!["retention_test/b", "retention_test/c"]);
+    }
+
+    #[tokio::test]
+    async fn test_dropped_events_are_not_retained() {
+        use super::*;
+
+        // The worker does not run before the test yields, so the queue is full
+        // after the first event
+        let namespace = Namespace::start(
+            Topic::parse("dropped_retention_test"),
+            NamespaceSettings::new().capacity(1).retention(10),
+        );
+        let sent = ["a", "b", "c", "d"]
+            .iter()
+            .map(|name| {
+                let event = DispatchedEvent::new(
+                    "{}".to_string(),
+                    format!("dropped_retention_test/{}", name),
+                );
+                let dispatch = DispatchSpan::open(&event);
+                namespace.send(event, dispatch)
+            })
+            .collect::<Vec<bool>>();
+
+        assert_eq!(sent, vec![true, false, false, false]);
+        let retained = namespace
+            .retained()
+            .iter()
+            .map(|e| e.name())
+            .collect::<Vec<String>>();
+        assert_eq!(retained, vec!["dropped_retention_test/a"]);
+    }
+}
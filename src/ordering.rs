@@ -0,0 +1,69 @@
+use crate::{event_listener::Registration, handler_id::HandlerId};
+
+/// Works out the order in which the registrations should be called
+///
+/// Handlers with a higher priority are called first. Handlers with the same
+/// priority are called in the order they were registered. `run_before` and
+/// `run_after` constraints take precedence over priorities. A constraint refers
+/// to other handlers by label or by name.
+///
+/// Returns the indexes of the registrations in calling order, or the handlers
+/// that could not be ordered because of a cycle
+pub(crate) fn execution_order(
+    registrations: &[&Registration],
+) -> Result<Vec<usize>, Vec<HandlerId>> {
+    let total = registrations.len();
+    let mut edges = vec![Vec::new(); total];
+    let mut in_degree = vec![0_usize; total];
+
+    let matching = |name: &str, me: usize| {
+        registrations
+            .iter()
+            .enumerate()
+            .filter(move |(index, r)| *index != me && r.answers_to(name))
+            .map(|(index, _)| index)
+            .collect::<Vec<usize>>()
+    };
+
+    for (index, registration) in registrations.iter().enumerate() {
+        for name in &registration.run_before {
+            for other in matching(name, index) {
+                edges[index].push(other);
+                in_degree[other] += 1;
+            }
+        }
+        for name in &registration.run_after {
+            for other in matching(name, index) {
+                edges[other].push(index);
+                in_degree[index] += 1;
+            }
+        }
+    }
+
+    let mut order = Vec::with_capacity(total);
+    let mut done = vec![false; total];
+    while order.len() < total {
+        // The ready registration with the highest priority. Ties go to the earliest registration
+        let next = (0..total)
+            .filter(|index| !done[*index] && in_degree[*index] == 0)
+            .min_by_key(|index| (std::cmp::Reverse(registrations[*index].priority), *index));
+
+        match next {
+            Some(index) => {
+                done[index] = true;
+                order.push(index);
+                for other in &edges[index] {
+                    in_degree[*other] -= 1;
+                }
+            }
+            None => {
+                return Err((0..total)
+                    .filter(|index| !done[*index])
+                    .map(|index| registrations[index].handler_id())
+                    .collect())
+            }
+        }
+    }
+
+    Ok(order)
+}
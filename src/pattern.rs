@@ -0,0 +1,221 @@
+//! Pattern based event names
+//!
+//! Event names are split into segments on `/`, like topics, and empty segments
+//! are skipped: `orders//*` is the same pattern as `orders/*`. A dot does not
+//! separate segments, so `user.*` is an exact name; a warning is logged when such
+//! a name is registered. A pattern is an event name where a segment is:
+//! - `*`: matches exactly one segment. `user/*` matches `user/created` but not `user/profile/updated`
+//! - `**`: matches one or more segments. `orders/**` matches `orders/created` and `orders/eu/shipped`
+//!
+//! The pattern `*` on its own is a catch-all and matches every event.
+//!
+//! Handlers registered for the exact name of an event are called first,
+//! followed by the handlers of its parent topics. The handlers of the matching
+//! patterns come next, starting with the most specific pattern: the pattern
+//! with the most literal segments, then the one with the fewest `**` segments.
+//! The catch-all handlers are called last. Within a name or a pattern, the
+//! handlers are ordered by priority and constraints.
+use crate::topic::SEPARATOR;
+use std::collections::{HashMap, HashSet};
+
+const ONE_SEGMENT: &str = "*";
+const MANY_SEGMENTS: &str = "**";
+
+/// The segments of the name, without the empty ones like `Topic::parse`
+fn segments_of(name: &str) -> impl Iterator<Item = &str> + Clone {
+    name.split(SEPARATOR).filter(|segment| !segment.is_empty())
+}
+
+fn is_wildcard(segment: &str) -> bool {
+    segment == ONE_SEGMENT || segment == MANY_SEGMENTS
+}
+
+/// Returns true if the name contains wildcard segments
+pub(crate) fn is_pattern(name: &str) -> bool {
+    segments_of(name).any(is_wildcard)
+}
+
+/// Returns true if the name is not a pattern but would be one if dots separated
+/// the segments, e.g. `user.*`
+pub(crate) fn is_dotted_pattern(name: &str) -> bool {
+    !is_pattern(name) && name.split(['.', SEPARATOR]).any(is_wildcard)
+}
+
+#[derive(Default)]
+struct Node {
+    literal: HashMap<String, Node>,
+    one: Option<Box<Node>>,
+    many: Option<Box<Node>>,
+    /// The registered names of the pattern, e.g. `orders/*` and `orders//*`
+    patterns: HashSet<String>,
+}
+
+/// Index of the registered patterns
+#[derive(Default)]
+pub(crate) struct PatternTrie {
+    root: Node,
+    catch_all: bool,
+}
+
+impl PatternTrie {
+    pub(crate) fn insert(&mut self, pattern: &str) {
+        if pattern == ONE_SEGMENT {
+            self.catch_all = true;
+            return;
+        }
+
+        let mut node = &mut self.root;
+        for segment in segments_of(pattern) {
+            node = match segment {
+                ONE_SEGMENT => node.one.get_or_insert_with(Default::default),
+                MANY_SEGMENTS => node.many.get_or_insert_with(Default::default),
+                _ => node.literal.entry(segment.to_string()).or_default(),
+            };
+        }
+        node.patterns.insert(pattern.to_string());
+    }
+
+    /// Removes the pattern and the nodes that no longer lead to a pattern
+    pub(crate) fn remove(&mut self, pattern: &str) {
+        if pattern == ONE_SEGMENT {
+            self.catch_all = false;
+            return;
+        }
+
+        let segments = segments_of(pattern).collect::<Vec<&str>>();
+        Self::remove_from(&mut self.root, pattern, &segments);
+    }
+
+    /// Returns true when the node no longer leads to a pattern
+    fn remove_from(node: &mut Node, pattern: &str, segments: &[&str]) -> bool {
+        match segments.split_first() {
+            None => {
+                node.patterns.remove(pattern);
+            }
+            Some((segment, rest)) => match *segment {
+                ONE_SEGMENT => {
+                    if node
+                        .one
+                        .as_mut()
+                        .is_some_and(|next| Self::remove_from(next, pattern, rest))
+                    {
+                        node.one = None;
+                    }
+                }
+                MANY_SEGMENTS => {
+                    if node
+                        .many
+                        .as_mut()
+                        .is_some_and(|next| Self::remove_from(next, pattern, rest))
+                    {
+                        node.many = None;
+                    }
+                }
+                _ => {
+                    if node
+                        .literal
+                        .get_mut(*segment)
+                        .is_some_and(|next| Self::remove_from(next, pattern, rest))
+                    {
+                        node.literal.remove(*segment);
+                    }
+                }
+            },
+        }
+
+        node.patterns.is_empty()
+            && node.one.is_none()
+            && node.many.is_none()
+            && node.literal.is_empty()
+    }
+
+    /// Returns the patterns matching the event name, most specific first
+    pub(crate) fn matching(&self, name: &str) -> Vec<String> {
+        let segments = segments_of(name).collect::<Vec<&str>>();
+        let mut found = HashSet::new();
+        Self::walk(&self.root, &segments, &mut found);
+
+        let mut patterns = found.into_iter().collect::<Vec<String>>();
+        patterns.sort_by_cached_key(|pattern| {
+            let parts = segments_of(pattern);
+            let literals = parts.clone().filter(|s| !is_wildcard(s)).count();
+            let many = parts.filter(|s| *s == MANY_SEGMENTS).count();
+            (std::cmp::Reverse(literals), many, pattern.clone())
+        });
+
+        if self.catch_all {
+            patterns.push(ONE_SEGMENT.to_string());
+        }
+
+        patterns
+    }
+
+    fn walk(node: &Node, segments: &[&str], found: &mut HashSet<String>) {
+        let Some((segment, rest)) = segments.split_first() else {
+            found.extend(node.patterns.iter().cloned());
+            return;
+        };
+
+        if let Some(next) = node.literal.get(*segment) {
+            Self::walk(next, rest, found);
+        }
+
+        if let Some(next) = &node.one {
+            Self::walk(next, rest, found);
+        }
+
+        if let Some(next) = &node.many {
+            for consumed in 1..=segments.len() {
+                Self::walk(next, &segments[consumed..], found);
+            }
+        }
+    }
+}
+
+mod test {
+    #[test]
+    fn test_pattern_matching() {
+        let mut trie = super::PatternTrie::default();
+        for pattern in ["user/*", "*/created", "orders/**", "orders/*/shipped", "*"] {
+            trie.insert(pattern);
+        }
+
+        assert_eq!(
+            trie.matching("user/created"),
+            vec!["*/created", "user/*", "*"]
+        );
+        assert_eq!(trie.matching("user/profile/updated"), vec!["*"]);
+        assert_eq!(
+            trie.matching("orders/eu/shipped"),
+            vec!["orders/*/shipped", "orders/**", "*"]
+        );
+        assert_eq!(trie.matching("orders"), vec!["*"]);
+        assert!(super::is_pattern("orders/**"));
+        assert!(!super::is_pattern("orders/created"));
+        assert!(super::is_dotted_pattern("user.*"));
+        assert!(super::is_dotted_pattern("orders.**"));
+        assert!(!super::is_dotted_pattern("user/*"));
+        assert!(!super::is_dotted_pattern("user.created"));
+
+        // Empty segments are skipped, like in topics
+        trie.insert("user//*");
+        assert_eq!(
+            trie.matching("user//created"),
+            vec!["*/created", "user/*", "user//*", "*"]
+        );
+        trie.remove("user//*");
+        assert_eq!(
+            trie.matching("user/created"),
+            vec!["*/created", "user/*", "*"]
+        );
+
+        trie.remove("orders/*/shipped");
+        assert_eq!(trie.matching("orders/eu/shipped"), vec!["orders/**", "*"]);
+        for pattern in ["user/*", "*/created", "orders/**", "*"] {
+            trie.remove(pattern);
+        }
+        assert!(!trie.catch_all);
+        assert!(trie.root.literal.is_empty() && trie.root.one.is_none());
+        assert!(trie.matching("user/created").is_empty());
+    }
+}
@@ -0,0 +1,117 @@
+use crate::event_listener::Queued;
+use std::{
+    collections::HashMap,
+    sync::{Mutex, PoisonError},
+};
+
+/// What `Paused::hold` did with an event
+// Every queued event is released when its name is not paused, so boxing it
+// would allocate on the common path
+#[allow(clippy::large_enum_variant)]
+#[derive(Debug)]
+pub(crate) enum Hold {
+    /// The name is not paused, the event is given back
+    Released(Queued),
+    Held,
+    /// The name already holds as many events as allowed
+    Dropped,
+}
+
+/// The paused event names and the events held while they are paused, with
+/// the span of their dispatching
+#[derive(Debug)]
+pub(crate) struct Paused {
+    capacity: usize,
+    events: Mutex<HashMap<String, Vec<Queued>>>,
+}
+
+impl Paused {
+    /// Holds at most `capacity` events per paused event name
+    pub(crate) fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            events: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Returns false when the event name was already paused
+    pub(crate) fn pause(&self, name: &str) -> bool {
+        let mut events = self.events.lock().unwrap_or_else(PoisonError::into_inner);
+        if events.contains_key(name) {
+            return false;
+        }
+        events.insert(name.to_string(), Vec::new());
+        true
+    }
+
+    /// Removes the pause and returns the held events, oldest first
+    pub(crate) fn resume(&self, name: &str) -> Option<Vec<Queued>> {
+        self.events
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .remove(name)
+    }
+
+    pub(crate) fn is_paused(&self, name: &str) -> bool {
+        self.events
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .contains_key(name)
+    }
+
+    /// Holds the event when its name is paused and there is room, otherwise gives it back
+    pub(crate) fn hold(&self, event: Queued) -> Hold {
+        let mut events = self.events.lock().unwrap_or_else(PoisonError::into_inner);
+        match events.get_mut(event.0.name_ref()) {
+            Some(held) if held.len() >= self.capacity => Hold::Dropped,
+            Some(held) => {
+                held.push(event);
+                Hold::Held
+            }
+            None => Hold::Released(event),
+        }
+    }
+
+    /// The number of held events per paused event name
+    pub(crate) fn counts(&self) -> HashMap<String, usize> {
+        self.events
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .iter()
+            .map(|(name, held)| (name.clone(), held.len()))
+            .collect()
+    }
+}
+
+mod test {
+    #[test]
+    fn test_paused() {
+        use super::*;
+        use crate::{dispatched_event::DispatchedEvent, trace_spans::DispatchSpan};
+
+        let paused = Paused::new(2);
+        let event = || {
+            let event = DispatchedEvent::new("{}".to_string(), "order.placed".to_string());
+            let dispatch = DispatchSpan::open(&event);
+            (event, dispatch)
+        };
+
+        assert!(matches!(paused.hold(event()), Hold::Released(_)));
+        assert!(paused.pause("order.placed"));
+        assert!(!paused.pause("order.placed"));
+        assert!(paused.is_paused("order.placed"));
+
+        assert!(matches!(paused.hold(event()), Hold::Held));
+        assert!(matches!(paused.hold(event()), Hold::Held));
+        assert!(matches!(paused.hold(event()), Hold::Dropped));
+        assert_eq!(paused.counts().get("order.placed"), Some(&2));
+
+        let held = paused.resume("order.placed").unwrap();
+        assert_eq!(held.len(), 2);
+        assert!(held
+            .iter()
+            .all(|(event, _)| event.name_ref() == "order.placed"));
+        assert!(paused.resume("order.placed").is_none());
+        assert!(matches!(paused.hold(event()), Hold::Released(_)));
+    }
+}
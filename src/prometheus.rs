@@ -0,0 +1,357 @@
+use crate::{
+    event_dispatcher::event_dispatcher,
+    http::{self, Response, ServerHandle},
+    stats::{Stats, OTHER_LABEL},
+};
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Write,
+    sync::{Arc, Mutex, PoisonError},
+    time::Duration,
+};
+use tokio::net::ToSocketAddrs;
+
+const DEFAULT_MAX_SERIES: usize = 100;
+
+/// Renders the statistics of the dispatcher in the Prometheus text format
+///
+/// Every metric keeps the first `max_series` label values it renders for as
+/// long as the exporter lives. The later values are added up under the
+/// `__other__` label, so that a series never moves from one scrape to the next
+/// and events dispatched with many different names do not create as many series
+/// ```
+/// # use orsomafo::{event_dispatcher, PrometheusExporter};
+/// # #[tokio::main]
+/// # async fn main() {
+///    let exporter = PrometheusExporter::new().max_series(50);
+///
+///    // Mount the text in your own server
+///    let text = exporter.render(&event_dispatcher().stats());
+///
+///    // Or serve it on `/metrics`
+///    let server = exporter.serve("127.0.0.1:0").await.unwrap();
+///    println!("metrics on http://{}/metrics", server.local_addr());
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct PrometheusExporter {
+    max_series: usize,
+    /// The label values kept per metric, shared by the clones of the exporter
+    admitted: Arc<Mutex<HashMap<&'static str, HashSet<String>>>>,
+}
+
+impl Default for PrometheusExporter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PrometheusExporter {
+    pub fn new() -> Self {
+        Self {
+            max_series: DEFAULT_MAX_SERIES,
+            admitted: Arc::default(),
+        }
+    }
+
+    /// Sets the maximum number of label values per metric. Defaults to 100
+    pub fn max_series(mut self, max_series: usize) -> Self {
+        self.max_series = max_series.max(1);
+        self
+    }
+
+    /// Renders the statistics in the Prometheus text format
+    pub fn render(&self, stats: &Stats) -> String {
+        let mut text = String::new();
+
+        for (name, help, counts) in [
+            (
+                "orsomafo_events_dispatched_total",
+                "The number of dispatched events",
+                stats.dispatched(),
+            ),
+            (
+                "orsomafo_events_handled_total",
+                "The number of events handled by at least one handler",
+                stats.handled(),
+            ),
+            (
+                "orsomafo_events_unhandled_total",
+                "The number of events that were not handled by any handler",
+                stats.unhandled(),
+            ),
+            (
+                "orsomafo_events_dropped_total",
+                "The number of events dropped because their queue was full or closed, or too many were held while paused",
+                stats.dropped(),
+            ),
+        ] {
+            header(&mut text, name, help, "counter");
+            for (event, count) in self.limit(name, counts) {
+                sample(&mut text, name, Some(("event", &event)), count);
+            }
+        }
+
+        let handlers = self.handlers(stats);
+        header(
+            &mut text,
+            "orsomafo_handler_duration_seconds",
+            "The time spent in the handler",
+            "summary",
+        );
+        for (handler, calls, _, _, total) in &handlers {
+            let label = Some(("handler", handler.as_str()));
+            sample(
+                &mut text,
+                "orsomafo_handler_duration_seconds_sum",
+                label,
+                total.as_secs_f64(),
+            );
+            sample(
+                &mut text,
+                "orsomafo_handler_duration_seconds_count",
+                label,
+                calls,
+            );
+        }
+
+        header(
+            &mut text,
+            "orsomafo_handler_failures_total",
+            "The number of times the handler panicked or returned an error",
+            "counter",
+        );
+        for (handler, _, failures, _, _) in &handlers {
+            sample(
+                &mut text,
+                "orsomafo_handler_failures_total",
+                Some(("handler", handler)),
+                failures,
+            );
+        }
+
+        header(
+            &mut text,
+            "orsomafo_handler_retries_total",
+            "The number of times a dead letter of the handler was redriven",
+            "counter",
+        );
+        for (handler, _, _, retries, _) in &handlers {
+            sample(
+                &mut text,
+                "orsomafo_handler_retries_total",
+                Some(("handler", handler)),
+                retries,
+            );
+        }
+
+        header(
+            &mut text,
+            "orsomafo_handlers_removed_total",
+            "The number of handlers removed at the end of their lifetime",
+            "counter",
+        );
+        sample(
+            &mut text,
+            "orsomafo_handlers_removed_total",
+            None,
+            stats.removed_handlers(),
+        );
+
+        header(
+            &mut text,
+            "orsomafo_queue_depth",
+            "The number of events waiting in the queues",
+            "gauge",
+        );
+        sample(&mut text, "orsomafo_queue_depth", None, stats.queue_depth());
+
+        text
+    }
+
+    /// Serves the statistics of the dispatcher on `/metrics`
+    pub async fn serve(self, addr: impl ToSocketAddrs) -> std::io::Result<ServerHandle> {
+        http::serve(
+            addr,
+            |_| None,
+            move |request| {
+                let response = match (request.method.as_str(), request.path.as_str()) {
+                    ("GET", "/metrics") => Response::new(
+                        200,
+                        "text/plain; version=0.0.4; charset=utf-8",
+                        self.render(&event_dispatcher().stats()),
+                    ),
+                    ("GET", _) => Response::text(404, "not found"),
+                    _ => Response::text(405, "method not allowed"),
+                };
+                async move { response }
+            },
+        )
+        .await
+    }
+
+    /// Keeps the counts of the admitted label values and adds up the rest
+    fn limit(&self, metric: &'static str, counts: &HashMap<String, u64>) -> Vec<(String, u64)> {
+        let admitted = self.admit(metric, counts.keys().map(String::as_str));
+        let mut limited = Vec::new();
+        let mut other = None;
+        for (value, count) in counts {
+            if admitted.contains(value) {
+                limited.push((value.clone(), *count));
+            } else {
+                *other.get_or_insert(0) += count;
+            }
+        }
+
+        limited.sort();
+        if let Some(other) = other {
+            limited.push((OTHER_LABEL.to_string(), other));
+        }
+        limited
+    }
+
+    /// The calls, failures, retries and total duration of the handlers, limited like `limit`
+    fn handlers(&self, stats: &Stats) -> Vec<(String, u64, u64, u64, Duration)> {
+        let admitted = self.admit(
+            "orsomafo_handler",
+            stats.handlers().iter().map(|handler| handler.handler()),
+        );
+        let mut handlers = Vec::new();
+        let mut other: Option<(String, u64, u64, u64, Duration)> = None;
+        for handler in stats.handlers() {
+            if admitted.contains(handler.handler()) {
+                handlers.push((
+                    handler.handler().to_string(),
+                    handler.calls(),
+                    handler.failures(),
+                    handler.retries(),
+                    handler.total_duration(),
+                ));
+            } else {
+                let sum =
+                    other.get_or_insert_with(|| (OTHER_LABEL.to_string(), 0, 0, 0, Duration::ZERO));
+                sum.1 += handler.calls();
+                sum.2 += handler.failures();
+                sum.3 += handler.retries();
+                sum.4 += handler.total_duration();
+            }
+        }
+
+        handlers.extend(other);
+        handlers
+    }
+
+    /// Admits the new label values of the metric while it has room.
+    /// Returns the admitted label values
+    fn admit<'a>(
+        &self,
+        metric: &'static str,
+        values: impl Iterator<Item = &'a str>,
+    ) -> HashSet<String> {
+        let mut values: Vec<&str> = values.filter(|value| *value != OTHER_LABEL).collect();
+        values.sort_unstable();
+
+        let mut admitted = self.admitted.lock().unwrap_or_else(PoisonError::into_inner);
+        let admitted = admitted.entry(metric).or_default();
+        for value in values {
+            if admitted.len() == self.max_series {
+                break;
+            }
+            admitted.insert(value.to_string());
+        }
+        admitted.clone()
+    }
+}
+
+fn header(text: &mut String, name: &str, help: &str, kind: &str) {
+    _ = writeln!(text, "# HELP {} {}", name, help);
+    _ = writeln!(text, "# TYPE {} {}", name, kind);
+}
+
+fn sample(
+    text: &mut String,
+    name: &str,
+    label: Option<(&str, &str)>,
+    value: impl std::fmt::Display,
+) {
+    match label {
+        Some((label, label_value)) => {
+            _ = writeln!(
+                text,
+                "{}{{{}=\"{}\"}} {}",
+                name,
+                label,
+                escape(label_value),
+                value
+            );
+        }
+        None => {
+            _ = writeln!(text, "{} {}", name, value);
+        }
+    }
+}
+
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+mod test {
+    #[tokio::test]
+    async fn test_prometheus_exporter() {
+        use super::*;
+        use crate::{handler_id::HandlerId, stats::Metrics};
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let metrics = Metrics::default();
+        let dispatch = |event: &str, times: usize| {
+            for _ in 0..times {
+                metrics.dispatched(event);
+            }
+        };
+        dispatch("order.placed", 3);
+        dispatch("order.paid", 2);
+        let handler = HandlerId::new(1, "audit".to_string(), Some("db".to_string()));
+        metrics.handler_called(&handler, Duration::from_millis(250), true);
+        metrics.handler_retried(&handler);
+
+        let exporter = PrometheusExporter::new().max_series(2);
+        let text = exporter.render(&metrics.snapshot(HashMap::new()));
+        assert!(text.contains("# TYPE orsomafo_events_dispatched_total counter\n"));
+        assert!(text.contains("orsomafo_events_dispatched_total{event=\"order.placed\"} 3\n"));
+        assert!(!text.contains("__other__"));
+
+        // The series already rendered are kept, even when a later one counts more
+        dispatch("order.\"x\"", 5);
+        let text = exporter.clone().render(&metrics.snapshot(HashMap::new()));
+        assert!(text.contains("orsomafo_events_dispatched_total{event=\"order.placed\"} 3\n"));
+        assert!(text.contains("orsomafo_events_dispatched_total{event=\"order.paid\"} 2\n"));
+        assert!(text.contains("orsomafo_events_dispatched_total{event=\"__other__\"} 5\n"));
+        assert!(!text.contains("order.\\\"x\\\""));
+        assert!(
+            text.contains("orsomafo_handler_duration_seconds_sum{handler=\"audit[db]\"} 0.25\n")
+        );
+        assert!(text.contains("orsomafo_handler_failures_total{handler=\"audit[db]\"} 1\n"));
+        assert!(text.contains("orsomafo_handler_retries_total{handler=\"audit[db]\"} 1\n"));
+        assert_eq!(escape("order.\"x\"\n"), "order.\\\"x\\\"\\n");
+
+        let server = PrometheusExporter::new()
+            .serve("127.0.0.1:0")
+            .await
+            .unwrap();
+        let mut stream = tokio::net::TcpStream::connect(server.local_addr())
+            .await
+            .unwrap();
+        stream
+            .write_all(b"GET /metrics HTTP/1.1\r\nhost: localhost\r\n\r\n")
+            .await
+            .unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(response.contains("orsomafo_queue_depth "));
+        server.shutdown();
+    }
+}
@@ -0,0 +1,183 @@
+use crate::{dispatched_event::DispatchedEvent, event::Outcome};
+use std::future::Future;
+
+/// The W3C trace context header with the trace and the parent span.
+/// Written when `TraceContextPropagator` is the global text map propagator
+pub const TRACEPARENT_HEADER: &str = "traceparent";
+/// The W3C trace context header with the vendor specific trace data
+pub const TRACESTATE_HEADER: &str = "tracestate";
+
+/// Writes the context into the headers of an event
+#[cfg(feature = "opentelemetry")]
+struct HeaderInjector<'a>(&'a mut DispatchedEvent);
+
+#[cfg(feature = "opentelemetry")]
+impl opentelemetry::propagation::Injector for HeaderInjector<'_> {
+    fn set(&mut self, key: &str, value: String) {
+        self.0.insert_header(key, value);
+    }
+}
+
+/// Reads the context from the headers of an event. Headers that are not strings are skipped
+#[cfg(feature = "opentelemetry")]
+struct HeaderExtractor<'a>(&'a DispatchedEvent);
+
+#[cfg(feature = "opentelemetry")]
+impl opentelemetry::propagation::Extractor for HeaderExtractor<'_> {
+    fn get(&self, key: &str) -> Option<&str> {
+        self.0.header_str(key)
+    }
+
+    fn keys(&self) -> Vec<&str> {
+        self.0
+            .headers()
+            .iter()
+            .filter(|(_, value)| value.is_string())
+            .map(|(key, _)| key.as_str())
+            .collect()
+    }
+}
+
+/// Writes the current OpenTelemetry context into the headers of the event, with
+/// the global text map propagator. Events that already carry a trace context keep it
+#[cfg(feature = "opentelemetry")]
+pub(crate) fn inject_context(event: &mut DispatchedEvent) {
+    use opentelemetry::{global, trace::TraceContextExt};
+
+    if extract_context(event).is_some() {
+        return;
+    }
+
+    let context = opentelemetry::Context::current();
+    if !context.span().span_context().is_valid() {
+        return;
+    }
+
+    global::get_text_map_propagator(|propagator| {
+        propagator.inject_context(&context, &mut HeaderInjector(event))
+    });
+}
+
+#[cfg(not(feature = "opentelemetry"))]
+pub(crate) fn inject_context(_event: &mut DispatchedEvent) {}
+
+/// Reads the trace context from the headers of the event, with the global text
+/// map propagator
+#[cfg(feature = "opentelemetry")]
+pub(crate) fn extract_context(event: &DispatchedEvent) -> Option<opentelemetry::Context> {
+    use opentelemetry::{global, trace::TraceContextExt};
+
+    // Extracted into an empty context, so that the current span is not mistaken
+    // for the one of the event
+    let context = global::get_text_map_propagator(|propagator| {
+        propagator.extract_with_context(&opentelemetry::Context::new(), &HeaderExtractor(event))
+    });
+    context.span().span_context().is_valid().then_some(context)
+}
+
+/// Runs the handler with the trace context of the event as the current context,
+/// so that the spans started by the handler continue the trace of the dispatcher
+#[cfg(feature = "opentelemetry")]
+pub(crate) async fn with_trace_context<F: Future<Output = Outcome>>(
+    future: F,
+    event: &DispatchedEvent,
+) -> Outcome {
+    use opentelemetry::context::FutureExt;
+
+    match extract_context(event) {
+        Some(context) => future.with_context(context).await,
+        None => future.await,
+    }
+}
+
+#[cfg(not(feature = "opentelemetry"))]
+pub(crate) async fn with_trace_context<F: Future<Output = Outcome>>(
+    future: F,
+    _event: &DispatchedEvent,
+) -> Outcome {
+    future.await
+}
+
+#[cfg(feature = "opentelemetry")]
+mod test {
+    #[tokio::test]
+    async fn test_trace_context_propagation() {
+        use super::*;
+        use crate::{event_dispatcher, Dispatchable};
+        use opentelemetry::{
+            global,
+            trace::{TraceContextExt, Tracer, TracerProvider},
+            Context,
+        };
+        use opentelemetry_sdk::{
+            propagation::TraceContextPropagator,
+            trace::{InMemorySpanExporter, SdkTracerProvider},
+        };
+        use std::sync::Mutex;
+
+        static TRACEPARENT: Mutex<Option<String>> = Mutex::new(None);
+
+        #[derive(Clone, serde::Serialize, serde::Deserialize)]
+        struct TracedOrder;
+        impl Dispatchable for TracedOrder {}
+
+        let exporter = InMemorySpanExporter::default();
+        let provider = SdkTracerProvider::builder()
+            .with_simple_exporter(exporter.clone())
+            .build();
+        global::set_tracer_provider(provider.clone());
+        global::set_text_map_propagator(TraceContextPropagator::new());
+
+        let _handle = TracedOrder::subscribe_fn(|event| {
+            Box::pin(async move {
+                *TRACEPARENT.lock().unwrap() =
+                    event.header_str(TRACEPARENT_HEADER).map(String::from);
+                global::tracer("handler").in_span("handle order", |_| {});
+            })
+        })
+        .await;
+
+        let request = provider.tracer("test").start("request");
+        let request_context = Context::current_with_span(request);
+        let request_span = request_context.span().span_context().clone();
+        {
+            let _attached = request_context.clone().attach();
+            event_dispatcher().dispatch_and_wait(TracedOrder).await;
+        }
+        request_context.span().end();
+        provider.force_flush().unwrap();
+
+        assert_eq!(
+            TRACEPARENT.lock().unwrap().take(),
+            Some(format!(
+                "00-{}-{}-01",
+                request_span.trace_id(),
+                request_span.span_id()
+            ))
+        );
+
+        let spans = exporter.get_finished_spans().unwrap();
+        let handled = spans
+            .iter()
+            .find(|span| span.name == "handle order")
+            .unwrap();
+        assert_eq!(handled.span_context.trace_id(), request_span.trace_id());
+        assert_eq!(handled.parent_span_id, request_span.span_id());
+
+        let mut event = DispatchedEvent::new("{}".to_string(), "remote".to_string());
+        event.insert_header(TRACEPARENT_HEADER, "00-not-a-trace-01");
+        assert!(extract_context(&event).is_none());
+
+        // Versions above 00 are read as well, as the W3C specification requires
+        let mut event = DispatchedEvent::new("{}".to_string(), "remote".to_string());
+        event.insert_header(
+            TRACEPARENT_HEADER,
+            "01-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01",
+        );
+        let context = extract_context(&event).unwrap();
+        assert_eq!(
+            context.span().span_context().trace_id().to_string(),
+            "4bf92f3577b34da6a3ce929d0e0e4736"
+        );
+    }
+}
@@ -0,0 +1,187 @@
+use crate::{handler_id::HandlerId, lifetime::Lifetime};
+use std::time::Duration;
+
+/// A registration as seen by `EventDispatcher::registry`
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct RegisteredHandler {
+    handler: HandlerId,
+    position: usize,
+    priority: i32,
+    run_before: Vec<String>,
+    run_after: Vec<String>,
+    lifetime: &'static str,
+    remaining_calls: Option<u32>,
+    expires_in: Option<Duration>,
+    expired: bool,
+    enabled: bool,
+    group: Option<String>,
+    filtered: bool,
+}
+
+impl RegisteredHandler {
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn new(
+        handler: HandlerId,
+        position: usize,
+        priority: i32,
+        run_before: Vec<String>,
+        run_after: Vec<String>,
+        lifetime: &Lifetime,
+        calls: u32,
+        enabled: bool,
+        group: Option<String>,
+        filtered: bool,
+    ) -> Self {
+        Self {
+            handler,
+            position,
+            priority,
+            run_before,
+            run_after,
+            lifetime: lifetime.kind(),
+            remaining_calls: lifetime.max_calls().map(|max| max.saturating_sub(calls)),
+            expires_in: lifetime.expires_in(),
+            expired: lifetime.has_expired(calls),
+            enabled,
+            group,
+            filtered,
+        }
+    }
+
+    /// The registration of the handler
+    pub fn handler(&self) -> &HandlerId {
+        &self.handler
+    }
+
+    /// The position of the handler in the calling order of the event, starting at 0
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    pub fn priority(&self) -> i32 {
+        self.priority
+    }
+
+    /// The handlers this handler is called before
+    pub fn run_before(&self) -> &[String] {
+        &self.run_before
+    }
+
+    /// The handlers this handler is called after
+    pub fn run_after(&self) -> &[String] {
+        &self.run_after
+    }
+
+    /// The kind of lifetime: `forever`, `once`, `times`, `until` or `until_event`
+    pub fn lifetime(&self) -> &str {
+        self.lifetime
+    }
+
+    /// The number of events the handler can still handle, when limited
+    pub fn remaining_calls(&self) -> Option<u32> {
+        self.remaining_calls
+    }
+
+    /// The time left before the registration expires, when it has a deadline
+    pub fn expires_in(&self) -> Option<Duration> {
+        self.expires_in
+    }
+
+    /// Returns true when the lifetime of the registration ended. The registration
+    /// is removed by the next event it applies to
+    pub fn has_expired(&self) -> bool {
+        self.expired
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// The `Subscriber` group the handler was registered with
+    pub fn group(&self) -> Option<&str> {
+        self.group.as_deref()
+    }
+
+    /// Returns true when the handler only receives the events accepted by a filter
+    pub fn is_filtered(&self) -> bool {
+        self.filtered
+    }
+}
+
+/// The handlers registered for an event name or a pattern
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct RegisteredEvent {
+    name: String,
+    pattern: bool,
+    handlers: Vec<RegisteredHandler>,
+}
+
+impl RegisteredEvent {
+    pub(crate) fn new(name: String, pattern: bool, handlers: Vec<RegisteredHandler>) -> Self {
+        Self {
+            name,
+            pattern,
+            handlers,
+        }
+    }
+
+    /// The event name, topic or pattern the handlers are registered for
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns true when the name is a pattern. See `EventDispatcherBuilder::listen_str`
+    pub fn is_pattern(&self) -> bool {
+        self.pattern
+    }
+
+    /// The handlers in calling order
+    pub fn handlers(&self) -> &[RegisteredHandler] {
+        &self.handlers
+    }
+}
+
+/// A snapshot of the registered handlers
+///
+/// Returned by `EventDispatcher::registry`
+/// ```
+/// # use orsomafo::{event_dispatcher, Dispatchable};
+/// # #[tokio::main]
+/// # async fn main() {
+///    #[derive(Clone, serde::Serialize, serde::Deserialize)]
+///    struct UserCreated;
+///    impl Dispatchable for UserCreated {}
+///
+///    let _handle = UserCreated::subscribe_fn(|_| Box::pin(async {})).await;
+///
+///    for event in event_dispatcher().registry().events() {
+///        println!("{}: {} handler(s)", event.name(), event.handlers().len());
+///    }
+/// # }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Serialize)]
+pub struct Registry {
+    events: Vec<RegisteredEvent>,
+}
+
+impl Registry {
+    pub(crate) fn new(mut events: Vec<RegisteredEvent>) -> Self {
+        events.sort_by(|a, b| a.name.cmp(&b.name));
+        Self { events }
+    }
+
+    /// The event names and patterns with at least one handler, sorted by name
+    pub fn events(&self) -> &[RegisteredEvent] {
+        &self.events
+    }
+
+    /// The handlers registered for the event name or pattern
+    pub fn event(&self, name: &str) -> Option<&RegisteredEvent> {
+        self.events.iter().find(|event| event.name == name)
+    }
+
+    /// The total number of registrations
+    pub fn handler_count(&self) -> usize {
+        self.events.iter().map(|event| event.handlers.len()).sum()
+    }
+}
@@ -0,0 +1,189 @@
+use crate::dispatched_event::DispatchedEvent;
+use std::{
+    collections::HashMap,
+    ops::Range,
+    sync::{Mutex, PoisonError},
+};
+
+/// How an observed sequence number relates to the ones seen before
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SequenceStatus {
+    /// The first sequence number seen by the consumer, or the one right after the last
+    InOrder,
+    /// The sequence numbers in the range were skipped
+    Gap { missing: Range<u64> },
+    /// A skipped sequence number that arrived late
+    Reordered,
+    /// The sequence number was already seen
+    Duplicate,
+}
+
+#[derive(Debug, Default)]
+struct Consumer {
+    last: u64,
+    // Skipped sequence numbers that have not been seen yet
+    missing: Vec<Range<u64>>,
+}
+
+impl Consumer {
+    fn forget_oldest_gaps(&mut self, max_gaps: usize) {
+        let excess = self.missing.len().saturating_sub(max_gaps);
+        self.missing.drain(..excess);
+    }
+}
+
+/// Tracks the last sequence number seen by each consumer and reports the
+/// gaps, duplicates and reordering
+///
+/// Each consumer keeps at most `max_gaps` gaps. When a lossy stream opens more,
+/// the oldest gaps are forgotten, and their sequence numbers are reported as
+/// duplicates if they arrive later
+/// ```
+/// # use orsomafo::{SequenceStatus, SequenceTracker};
+///    let tracker = SequenceTracker::new();
+///
+///    assert_eq!(tracker.observe("projection", 1), SequenceStatus::InOrder);
+///    assert_eq!(tracker.observe("projection", 4), SequenceStatus::Gap { missing: 2..4 });
+///    assert_eq!(tracker.observe("projection", 3), SequenceStatus::Reordered);
+///    assert_eq!(tracker.observe("projection", 4), SequenceStatus::Duplicate);
+///    assert_eq!(tracker.missing("projection"), vec![2..3]);
+/// ```
+#[derive(Debug)]
+pub struct SequenceTracker {
+    max_gaps: usize,
+    consumers: Mutex<HashMap<String, Consumer>>,
+}
+
+const DEFAULT_MAX_GAPS: usize = 1024;
+
+impl Default for SequenceTracker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SequenceTracker {
+    pub fn new() -> Self {
+        Self {
+            max_gaps: DEFAULT_MAX_GAPS,
+            consumers: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Sets the maximum number of gaps kept per consumer. Defaults to 1024
+    pub fn max_gaps(mut self, max_gaps: usize) -> Self {
+        self.max_gaps = max_gaps;
+        self
+    }
+
+    /// Records the sequence number of the event for the consumer
+    pub fn observe_event(&self, consumer: &str, event: &DispatchedEvent) -> SequenceStatus {
+        self.observe(consumer, event.sequence())
+    }
+
+    /// Records the sequence number for the consumer
+    pub fn observe(&self, consumer: &str, sequence: u64) -> SequenceStatus {
+        let mut consumers = self
+            .consumers
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        let Some(state) = consumers.get_mut(consumer) else {
+            consumers.insert(
+                consumer.to_string(),
+                Consumer {
+                    last: sequence,
+                    missing: Vec::new(),
+                },
+            );
+            return SequenceStatus::InOrder;
+        };
+
+        if sequence == state.last + 1 {
+            state.last = sequence;
+            return SequenceStatus::InOrder;
+        }
+
+        if sequence > state.last {
+            let missing = state.last + 1..sequence;
+            state.missing.push(missing.clone());
+            state.last = sequence;
+            state.forget_oldest_gaps(self.max_gaps);
+            return SequenceStatus::Gap { missing };
+        }
+
+        match state.missing.iter().position(|r| r.contains(&sequence)) {
+            Some(index) => {
+                let range = state.missing.remove(index);
+                for part in [range.start..sequence, sequence + 1..range.end] {
+                    if !part.is_empty() {
+                        state.missing.push(part);
+                    }
+                }
+                state.missing.sort_by_key(|r| r.start);
+                state.forget_oldest_gaps(self.max_gaps);
+                SequenceStatus::Reordered
+            }
+            None => SequenceStatus::Duplicate,
+        }
+    }
+
+    /// The last sequence number seen by the consumer
+    pub fn last(&self, consumer: &str) -> Option<u64> {
+        self.consumers
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(consumer)
+            .map(|state| state.last)
+    }
+
+    /// The sequence numbers the consumer skipped and has not seen since
+    pub fn missing(&self, consumer: &str) -> Vec<Range<u64>> {
+        self.consumers
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(consumer)
+            .map(|state| state.missing.clone())
+            .unwrap_or_default()
+    }
+
+    /// Forgets what the consumer has seen
+    pub fn reset(&self, consumer: &str) {
+        self.consumers
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .remove(consumer);
+    }
+}
+
+mod test {
+    #[test]
+    fn test_sequence_tracker() {
+        use super::*;
+
+        let tracker = SequenceTracker::new();
+        assert_eq!(tracker.observe("a", 10), SequenceStatus::InOrder);
+        assert_eq!(
+            tracker.observe("a", 15),
+            SequenceStatus::Gap { missing: 11..15 }
+        );
+        assert_eq!(tracker.observe("a", 12), SequenceStatus::Reordered);
+        assert_eq!(tracker.observe("a", 12), SequenceStatus::Duplicate);
+        assert_eq!(tracker.observe("a", 9), SequenceStatus::Duplicate);
+        assert_eq!(tracker.missing("a"), vec![11..12, 13..15]);
+        assert_eq!(tracker.last("a"), Some(15));
+
+        // Consumers are tracked separately
+        assert_eq!(tracker.observe("b", 12), SequenceStatus::InOrder);
+        tracker.reset("a");
+        assert!(tracker.missing("a").is_empty());
+
+        // The oldest gaps are forgotten
+        let tracker = SequenceTracker::new().max_gaps(2);
+        for sequence in [1, 3, 5, 7] {
+            tracker.observe("a", sequence);
+        }
+        assert_eq!(tracker.missing("a"), vec![4..5, 6..7]);
+        assert_eq!(tracker.observe("a", 2), SequenceStatus::Duplicate);
+        assert_eq!(tracker.observe("a", 4), SequenceStatus::Reordered);
+    }
+}
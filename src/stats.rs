@@ -0,0 +1,383 @@
+use crate::{event_dispatcher::EVENT_DISPATCHER, handler_id::HandlerId};
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicI64, Ordering},
+        Mutex, PoisonError,
+    },
+    time::Duration,
+};
+
+/// The label value under which the event names and handlers over the limit are counted
+pub const OTHER_LABEL: &str = "__other__";
+
+/// The number of event names or handlers counted on their own per statistic.
+/// The later ones are counted together under `OTHER_LABEL`
+pub(crate) const MAX_TRACKED: usize = 1_000;
+
+/// The key to count the name under: the name itself while the map has room
+pub(crate) fn tracked<'a, V>(map: &HashMap<String, V>, name: &'a str) -> &'a str {
+    if map.len() < MAX_TRACKED || map.contains_key(name) {
+        name
+    } else {
+        OTHER_LABEL
+    }
+}
+
+/// Adds one to the count of the name, or of `OTHER_LABEL` when the map is full.
+/// Returns the key that was counted
+pub(crate) fn count<'a>(map: &mut HashMap<String, u64>, name: &'a str) -> &'a str {
+    let key = tracked(map, name);
+    *map.entry(key.to_string()).or_default() += 1;
+    key
+}
+
+/// How often a handler was called and how long it took
+///
+/// The registrations of a handler with the same name and label are counted
+/// together, so that the statistics outlive the registrations
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct HandlerStats {
+    handler: String,
+    calls: u64,
+    failures: u64,
+    retries: u64,
+    total_duration: Duration,
+    max_duration: Duration,
+}
+
+impl HandlerStats {
+    fn new(handler: String) -> Self {
+        Self {
+            handler,
+            calls: 0,
+            failures: 0,
+            retries: 0,
+            total_duration: Duration::ZERO,
+            max_duration: Duration::ZERO,
+        }
+    }
+
+    /// The name of the handler, followed by its label in brackets when it has one
+    pub fn handler(&self) -> &str {
+        &self.handler
+    }
+
+    /// The number of times the handler was called
+    pub fn calls(&self) -> u64 {
+        self.calls
+    }
+
+    /// The number of times the handler panicked or returned an error
+    pub fn failures(&self) -> u64 {
+        self.failures
+    }
+
+    /// The number of times a dead letter of the handler was redriven.
+    /// See `EventDispatcher::redrive`
+    pub fn retries(&self) -> u64 {
+        self.retries
+    }
+
+    /// The time spent in the handler over all of the calls
+    pub fn total_duration(&self) -> Duration {
+        self.total_duration
+    }
+
+    /// The longest call of the handler
+    pub fn max_duration(&self) -> Duration {
+        self.max_duration
+    }
+
+    /// The average duration of a call of the handler
+    pub fn mean_duration(&self) -> Duration {
+        match self.calls {
+            0 => Duration::ZERO,
+            calls => {
+                let nanos = self.total_duration.as_nanos() / u128::from(calls);
+                Duration::from_nanos(u64::try_from(nanos).unwrap_or(u64::MAX))
+            }
+        }
+    }
+}
+
+/// A snapshot of what the dispatcher has done since it was created
+///
+/// Returned by `EventDispatcher::stats`
+#[derive(Debug, Clone, Default, serde::Serialize)]
+pub struct Stats {
+    dispatched: HashMap<String, u64>,
+    handled: HashMap<String, u64>,
+    unhandled: HashMap<String, u64>,
+    dropped: HashMap<String, u64>,
+    handlers: Vec<HandlerStats>,
+    removed_handlers: u64,
+    queue_depth: u64,
+}
+
+impl Stats {
+    /// The number of dispatched events, per event name
+    pub fn dispatched(&self) -> &HashMap<String, u64> {
+        &self.dispatched
+    }
+
+    /// The number of events handled by at least one handler, per event name
+    pub fn handled(&self) -> &HashMap<String, u64> {
+        &self.handled
+    }
+
+    /// The number of events that were not handled by any handler, per event name
+    pub fn unhandled(&self) -> &HashMap<String, u64> {
+        &self.unhandled
+    }
+
+    /// The number of events dropped because their queue was full or closed,
+    /// because too many events were held while their name was paused, or because
+    /// they were dispatched in the current thread while paused, per event name
+    pub fn dropped(&self) -> &HashMap<String, u64> {
+        &self.dropped
+    }
+
+    /// The calls of every handler that was called, sorted by handler
+    pub fn handlers(&self) -> &[HandlerStats] {
+        &self.handlers
+    }
+
+    /// The calls of the registrations with the same name and label as the handler
+    pub fn handler(&self, handler: &HandlerId) -> Option<&HandlerStats> {
+        let series = handler.series();
+        self.handlers.iter().find(|stats| stats.handler == series)
+    }
+
+    /// The number of handlers removed at the end of their lifetime
+    pub fn removed_handlers(&self) -> u64 {
+        self.removed_handlers
+    }
+
+    /// The number of events waiting in the queues
+    pub fn queue_depth(&self) -> u64 {
+        self.queue_depth
+    }
+}
+
+#[derive(Debug, Default)]
+struct Collected {
+    dispatched: HashMap<String, u64>,
+    handled: HashMap<String, u64>,
+    dropped: HashMap<String, u64>,
+    handlers: HashMap<String, HandlerStats>,
+    removed_handlers: u64,
+}
+
+impl Collected {
+    /// The statistics of the handler series, or of `OTHER_LABEL` when too many are counted
+    fn handler(&mut self, handler: &HandlerId) -> (String, &mut HandlerStats) {
+        let series = tracked(&self.handlers, &handler.series()).to_string();
+        let stats = self
+            .handlers
+            .entry(series.clone())
+            .or_insert_with(|| HandlerStats::new(series.clone()));
+        (series, stats)
+    }
+}
+
+/// Collects the statistics of the dispatcher and reports them to the
+/// `metrics` facade when the feature is enabled
+#[derive(Debug, Default)]
+pub(crate) struct Metrics {
+    collected: Mutex<Collected>,
+    queue_depth: AtomicI64,
+}
+
+impl Metrics {
+    fn collected(&self) -> std::sync::MutexGuard<'_, Collected> {
+        self.collected
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    pub(crate) fn dispatched(&self, name: &str) {
+        let _name = count(&mut self.collected().dispatched, name);
+        #[cfg(feature = "metrics")]
+        metrics::counter!("orsomafo_events_dispatched_total", "event" => _name.to_string())
+            .increment(1);
+    }
+
+    pub(crate) fn handled(&self, name: &str) {
+        let _name = count(&mut self.collected().handled, name);
+        #[cfg(feature = "metrics")]
+        metrics::counter!("orsomafo_events_handled_total", "event" => _name.to_string())
+            .increment(1);
+    }
+
+    pub(crate) fn dropped(&self, name: &str) {
+        let _name = count(&mut self.collected().dropped, name);
+        #[cfg(feature = "metrics")]
+        metrics::counter!("orsomafo_events_dropped_total", "event" => _name.to_string())
+            .increment(1);
+    }
+
+    pub(crate) fn handler_called(&self, handler: &HandlerId, took: Duration, failed: bool) {
+        let _series = {
+            let mut collected = self.collected();
+            let (series, stats) = collected.handler(handler);
+            stats.calls += 1;
+            stats.total_duration += took;
+            stats.max_duration = stats.max_duration.max(took);
+            if failed {
+                stats.failures += 1;
+            }
+            series
+        };
+
+        #[cfg(feature = "metrics")]
+        {
+            metrics::histogram!("orsomafo_handler_duration_seconds", "handler" => _series.clone())
+                .record(took.as_secs_f64());
+            if failed {
+                metrics::counter!("orsomafo_handler_failures_total", "handler" => _series)
+                    .increment(1);
+            }
+        }
+    }
+
+    pub(crate) fn handler_retried(&self, handler: &HandlerId) {
+        let _series = {
+            let mut collected = self.collected();
+            let (series, stats) = collected.handler(handler);
+            stats.retries += 1;
+            series
+        };
+        #[cfg(feature = "metrics")]
+        metrics::counter!("orsomafo_handler_retries_total", "handler" => _series).increment(1);
+    }
+
+    pub(crate) fn handler_removed(&self) {
+        self.collected().removed_handlers += 1;
+        #[cfg(feature = "metrics")]
+        metrics::counter!("orsomafo_handlers_removed_total").increment(1);
+    }
+
+    pub(crate) fn queued(&self) {
+        let _depth = self.queue_depth.fetch_add(1, Ordering::SeqCst) + 1;
+        #[cfg(feature = "metrics")]
+        metrics::gauge!("orsomafo_queue_depth").set(_depth.max(0) as f64);
+    }
+
+    pub(crate) fn dequeued(&self) {
+        let _depth = self.queue_depth.fetch_sub(1, Ordering::SeqCst) - 1;
+        #[cfg(feature = "metrics")]
+        metrics::gauge!("orsomafo_queue_depth").set(_depth.max(0) as f64);
+    }
+
+    pub(crate) fn snapshot(&self, unhandled: HashMap<String, u64>) -> Stats {
+        let collected = self.collected();
+        let mut handlers: Vec<HandlerStats> = collected.handlers.values().cloned().collect();
+        handlers.sort_by(|a, b| a.handler.cmp(&b.handler));
+
+        Stats {
+            dispatched: collected.dispatched.clone(),
+            handled: collected.handled.clone(),
+            unhandled,
+            dropped: collected.dropped.clone(),
+            handlers,
+            removed_handlers: collected.removed_handlers,
+            queue_depth: self.queue_depth.load(Ordering::SeqCst).max(0) as u64,
+        }
+    }
+}
+
+/// Records into the statistics of the dispatcher, once it is created
+pub(crate) fn record(record: impl FnOnce(&Metrics)) {
+    if let Some(dispatcher) = EVENT_DISPATCHER.get() {
+        record(dispatcher.metrics());
+    }
+}
+
+mod test {
+    #[test]
+    fn test_metrics() {
+        use super::*;
+
+        let metrics = Metrics::default();
+        let handler = HandlerId::new(1, "audit".to_string(), None);
+        // Registered again, e.g. after a subscription handle was dropped
+        let registered_again = HandlerId::new(2, "audit".to_string(), None);
+        let record = || {
+            metrics.dispatched("user.created");
+            metrics.queued();
+            metrics.dequeued();
+            metrics.handler_called(&handler, Duration::from_millis(10), false);
+            metrics.handler_called(&registered_again, Duration::from_millis(30), true);
+            metrics.handler_retried(&registered_again);
+            metrics.handled("user.created");
+            metrics.dropped("user.deleted");
+            metrics.handler_removed();
+        };
+
+        #[cfg(feature = "metrics")]
+        let snapshotter = {
+            let recorder = metrics_util::debugging::DebuggingRecorder::new();
+            let snapshotter = recorder.snapshotter();
+            metrics::with_local_recorder(&recorder, record);
+            snapshotter
+        };
+        #[cfg(not(feature = "metrics"))]
+        record();
+
+        let stats = metrics.snapshot(HashMap::new());
+        assert_eq!(stats.dispatched().get("user.created"), Some(&1));
+        assert_eq!(stats.handled().get("user.created"), Some(&1));
+        assert_eq!(stats.dropped().get("user.deleted"), Some(&1));
+        assert_eq!(stats.removed_handlers(), 1);
+        assert_eq!(stats.queue_depth(), 0);
+
+        assert_eq!(stats.handlers().len(), 1);
+        let handler_stats = stats.handler(&handler).unwrap();
+        assert_eq!(handler_stats.handler(), "audit");
+        assert_eq!(handler_stats.calls(), 2);
+        assert_eq!(handler_stats.failures(), 1);
+        assert_eq!(handler_stats.retries(), 1);
+        assert_eq!(handler_stats.mean_duration(), Duration::from_millis(20));
+        assert_eq!(handler_stats.max_duration(), Duration::from_millis(30));
+
+        // The names over the limit are counted together
+        for i in 0..MAX_TRACKED + 5 {
+            metrics.dispatched(&format!("event.{}", i));
+        }
+        metrics.dispatched("user.created");
+        let dispatched = metrics.snapshot(HashMap::new()).dispatched().clone();
+        assert_eq!(dispatched.len(), MAX_TRACKED + 1);
+        assert_eq!(dispatched.get("user.created"), Some(&2));
+        assert_eq!(dispatched.get(OTHER_LABEL), Some(&6));
+
+        #[cfg(feature = "metrics")]
+        {
+            use metrics_util::debugging::DebugValue;
+
+            let recorded = snapshotter.snapshot().into_vec();
+            let value_of = |name: &str| {
+                recorded
+                    .iter()
+                    .find(|(key, ..)| key.key().name() == name)
+                    .map(|(.., value)| value)
+            };
+            assert_eq!(
+                value_of("orsomafo_events_dispatched_total"),
+                Some(&DebugValue::Counter(1))
+            );
+            assert_eq!(
+                value_of("orsomafo_handler_failures_total"),
+                Some(&DebugValue::Counter(1))
+            );
+            assert_eq!(
+                value_of("orsomafo_handler_retries_total"),
+                Some(&DebugValue::Counter(1))
+            );
+            assert!(matches!(
+                value_of("orsomafo_handler_duration_seconds"),
+                Some(DebugValue::Histogram(values)) if values.len() == 2
+            ));
+        }
+    }
+}
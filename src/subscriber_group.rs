@@ -0,0 +1,57 @@
+use crate::event_listener::{is_group_enabled, remove_group, set_group_enabled};
+
+/// Manages all of the registrations made by a `Subscriber`
+///
+/// ```
+/// # use orsomafo::{Dispatchable, DispatchedEvent, Subscriber};
+/// # #[tokio::main]
+/// # async fn main() {
+///    #[derive(Clone, serde::Serialize, serde::Deserialize)]
+///    struct MyEvent;
+///    impl Dispatchable for MyEvent {}
+///
+///    let group = Subscriber::named("billing")
+///        .listen_fn::<MyEvent>(|_| Box::pin(async {}))
+///        .build()
+///        .await;
+///
+///    group.disable(); // Handlers in the group are skipped
+///    group.enable();
+///    group.remove(); // Handlers in the group are removed
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct SubscriberGroup {
+    name: String,
+}
+
+impl SubscriberGroup {
+    pub(crate) fn new(name: String) -> Self {
+        Self { name }
+    }
+
+    /// The name of the group
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Stops calling the group's handlers until `enable` is called
+    pub fn disable(&self) {
+        set_group_enabled(&self.name, false);
+    }
+
+    /// Resumes calling the group's handlers
+    pub fn enable(&self) {
+        set_group_enabled(&self.name, true);
+    }
+
+    /// Returns true if at least one of the group's handlers is enabled
+    pub fn is_enabled(&self) -> bool {
+        is_group_enabled(&self.name)
+    }
+
+    /// Removes all of the group's handlers
+    pub fn remove(self) {
+        remove_group(&self.name);
+    }
+}
@@ -0,0 +1,75 @@
+use crate::{event_listener::unsubscribe_registration, handler_id::HandlerId};
+
+/// Returned when subscribing to an event.
+///
+/// The subscription lives for as long as the handle is alive. Dropping the handle
+/// or calling `unsubscribe` removes exactly this registration. Call `detach` to
+/// keep the handler registered for the lifetime of the application.
+/// ```
+/// # use orsomafo::{Dispatchable, DispatchedEvent};
+/// # #[tokio::main]
+/// # async fn main() {
+///    #[derive(Clone, serde::Serialize, serde::Deserialize)]
+///    struct MyEvent;
+///    impl Dispatchable for MyEvent {}
+///
+///    let handle = MyEvent::subscribe_fn(|event: DispatchedEvent| {
+///        Box::pin(async move { println!("handled: {}", event.name()) })
+///    })
+///    .await;
+///
+///    // ...
+///    handle.unsubscribe();
+/// # }
+/// ```
+#[derive(Debug)]
+#[must_use = "dropping the handle removes the subscription, call `detach` to keep it"]
+pub struct SubscriptionHandle {
+    event: String,
+    handler_id: HandlerId,
+    active: bool,
+}
+
+impl SubscriptionHandle {
+    pub(crate) fn new(event: String, handler_id: HandlerId) -> Self {
+        Self {
+            event,
+            handler_id,
+            active: true,
+        }
+    }
+
+    /// The unique ID of this registration
+    pub fn id(&self) -> u64 {
+        self.handler_id.registration()
+    }
+
+    /// The full identity of the registered handler
+    pub fn handler_id(&self) -> &HandlerId {
+        &self.handler_id
+    }
+
+    /// The name of the event subscribed to
+    pub fn event_name(&self) -> &str {
+        &self.event
+    }
+
+    /// Removes the registration
+    pub fn unsubscribe(mut self) {
+        self.active = false;
+        unsubscribe_registration(self.event.clone(), self.id());
+    }
+
+    /// Keeps the registration alive for the lifetime of the application
+    pub fn detach(mut self) {
+        self.active = false;
+    }
+}
+
+impl Drop for SubscriptionHandle {
+    fn drop(&mut self) {
+        if self.active {
+            unsubscribe_registration(std::mem::take(&mut self.event), self.id());
+        }
+    }
+}
@@ -0,0 +1,71 @@
+use std::fmt::Display;
+
+pub(crate) const SEPARATOR: char = '/';
+
+/// A structured event name such as `billing/invoice/paid`
+///
+/// Handlers registered for a topic also receive the events of its child topics.
+/// A handler registered for `billing` receives `billing/invoice/paid`. Handlers
+/// registered for the exact topic are called first, followed by the handlers
+/// of the parent topics, starting with the closest parent.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Topic {
+    segments: Vec<String>,
+}
+
+impl Topic {
+    /// Parses the event name into a topic. Empty segments are ignored
+    pub fn parse(name: &str) -> Self {
+        Self {
+            segments: name
+                .split(SEPARATOR)
+                .filter(|segment| !segment.is_empty())
+                .map(String::from)
+                .collect(),
+        }
+    }
+
+    /// The parts of the topic
+    pub fn segments(&self) -> &[String] {
+        &self.segments
+    }
+
+    /// The direct parent of this topic
+    pub fn parent(&self) -> Option<Topic> {
+        if self.segments.len() > 1 {
+            Some(Self {
+                segments: self.segments[..self.segments.len() - 1].to_vec(),
+            })
+        } else {
+            None
+        }
+    }
+
+    /// All of the parents of this topic, starting with the direct parent
+    pub fn ancestors(&self) -> Vec<Topic> {
+        let mut ancestors = Vec::new();
+        let mut current = self.parent();
+        while let Some(topic) = current {
+            current = topic.parent();
+            ancestors.push(topic);
+        }
+        ancestors
+    }
+
+    /// Returns true if this topic is the other topic or one of its children
+    pub fn is_within(&self, other: &Topic) -> bool {
+        self.segments.starts_with(&other.segments)
+    }
+}
+
+impl Display for Topic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.segments.join(&SEPARATOR.to_string()))
+    }
+}
+
+impl From<&str> for Topic {
+    fn from(name: &str) -> Self {
+        Self::parse(name)
+    }
+}
@@ -0,0 +1,217 @@
+use crate::{
+    dispatched_event::DispatchedEvent,
+    event::{EventHandler, Outcome},
+    event_listener::LOG_TITLE,
+};
+use async_trait::async_trait;
+use futures::future::BoxFuture;
+use std::{
+    convert::Infallible,
+    sync::Arc,
+    task::{Context, Poll},
+};
+use tokio::sync::Mutex;
+use tower::{BoxError, Service, ServiceExt};
+
+/// Uses a `tower::Service` as a handler
+///
+/// Created by `service_handler`
+pub struct ServiceHandler<S> {
+    label: String,
+    service: Mutex<S>,
+}
+
+/// Uses the `tower::Service` as a handler, so that it can be wrapped with
+/// `tower::ServiceBuilder` layers. The label is used as the name of the handler.
+///
+/// Every event is passed to the same service, so stateful layers such as
+/// `RateLimit` or `ConcurrencyLimit` see all of the events. The service is locked
+/// while it becomes ready and is called, but not while its response is awaited.
+/// An error returned by the service fails the handler, see `EventHandler::try_handle_event`
+/// ```
+/// # use orsomafo::{service_handler, Dispatchable, DispatchedEvent};
+/// # use tower::{service_fn, ServiceBuilder};
+/// # #[tokio::main]
+/// # async fn main() {
+///    #[derive(Clone, serde::Serialize, serde::Deserialize)]
+///    struct OrderPlaced;
+///    impl Dispatchable for OrderPlaced {}
+///
+///    let service = ServiceBuilder::new()
+///        .map_request(|event: DispatchedEvent| event)
+///        .service(service_fn(|event: DispatchedEvent| async move {
+///            println!("order placed: {}", event.id());
+///            Ok::<_, std::convert::Infallible>(())
+///        }));
+///
+///    let _handle = OrderPlaced::subscribe_with(service_handler("print_order", service)).await;
+/// # }
+/// ```
+pub fn service_handler<S>(label: &str, service: S) -> ServiceHandler<S>
+where
+    S: Service<DispatchedEvent> + Send + 'static,
+    S::Future: Send,
+    S::Error: Into<BoxError>,
+{
+    ServiceHandler {
+        label: label.to_string(),
+        service: Mutex::new(service),
+    }
+}
+
+#[async_trait]
+impl<S> EventHandler for ServiceHandler<S>
+where
+    S: Service<DispatchedEvent> + Send + 'static,
+    S::Future: Send,
+    S::Error: Into<BoxError>,
+{
+    async fn handle(&self, event: DispatchedEvent) {
+        let name = event.name();
+        if let Err(error) = self.try_handle_event(event).await {
+            log::error!(
+                target: LOG_TITLE,
+                "service handler: {}, failed to handle event: {:?}, {}",
+                &self.label,
+                &name,
+                error
+            );
+        }
+    }
+
+    async fn try_handle_event(&self, event: DispatchedEvent) -> Result<Outcome, String> {
+        let response = {
+            let mut service = self.service.lock().await;
+            let service = service
+                .ready()
+                .await
+                .map_err(|error| error.into().to_string())?;
+            service.call(event)
+        };
+
+        response
+            .await
+            .map(|_| Outcome::Continue)
+            .map_err(|error| error.into().to_string())
+    }
+
+    fn handler_id(&self) -> String {
+        self.label.clone()
+    }
+}
+
+/// Exposes a handler as a `tower::Service` that responds with the handler's `Outcome`
+/// ```
+/// # use orsomafo::{handler_fn, DispatchedEvent, HandlerService, Outcome};
+/// # use tower::ServiceExt;
+/// # #[tokio::main]
+/// # async fn main() {
+/// #  let event: DispatchedEvent = serde_json::from_str(
+/// #      r#"{"id":"01a152ed-f957-73b2-bc2a-43d4c20ddb1f","created_at":1792392690,"data":"{}","name":"ping"}"#,
+/// #  ).unwrap();
+///    let service = HandlerService::new(handler_fn(|_| Box::pin(async {})));
+///
+///    let outcome = service.oneshot(event).await.unwrap();
+///    assert_eq!(outcome, Outcome::Continue);
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct HandlerService {
+    handler: Arc<dyn EventHandler>,
+}
+
+impl HandlerService {
+    pub fn new(handler: impl EventHandler) -> Self {
+        Self {
+            handler: Arc::new(handler),
+        }
+    }
+}
+
+impl Service<DispatchedEvent> for HandlerService {
+    type Response = Outcome;
+    type Error = Infallible;
+    type Future = BoxFuture<'static, Result<Outcome, Infallible>>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, event: DispatchedEvent) -> Self::Future {
+        let handler = self.handler.clone();
+        Box::pin(async move { Ok(handler.handle_event(event).await) })
+    }
+}
+
+mod test {
+    #[tokio::test]
+    async fn test_tower_services() {
+        use super::*;
+        use crate::{event_dispatcher, Dispatchable};
+        use std::sync::atomic::{AtomicU32, Ordering};
+        use tower::{service_fn, ServiceBuilder};
+
+        #[derive(Clone, serde::Serialize, serde::Deserialize)]
+        struct TowerEvent {
+            id: u32,
+        }
+        impl Dispatchable for TowerEvent {}
+
+        static TOTAL: AtomicU32 = AtomicU32::new(0);
+
+        let service = ServiceBuilder::new()
+            .map_request(|event: DispatchedEvent| event.the_event::<TowerEvent>().unwrap().id)
+            .service(service_fn(|id: u32| async move {
+                if id == 0 {
+                    return Err("zero is not allowed");
+                }
+                TOTAL.fetch_add(id, Ordering::SeqCst);
+                Ok(())
+            }));
+        let handle = TowerEvent::subscribe_with(service_handler("total", service)).await;
+        assert_eq!(handle.handler_id().name(), "total");
+
+        // The same service is called for every event, so it can keep state
+        struct Counter {
+            calls: u32,
+        }
+
+        impl Service<DispatchedEvent> for Counter {
+            type Response = ();
+            type Error = Infallible;
+            type Future = futures::future::Ready<Result<(), Infallible>>;
+
+            fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+                Poll::Ready(Ok(()))
+            }
+
+            fn call(&mut self, _event: DispatchedEvent) -> Self::Future {
+                self.calls += 1;
+                CALLS.store(self.calls, Ordering::SeqCst);
+                futures::future::ready(Ok(()))
+            }
+        }
+
+        static CALLS: AtomicU32 = AtomicU32::new(0);
+        let _counter =
+            TowerEvent::subscribe_with(service_handler("counter", Counter { calls: 0 })).await;
+
+        let mut failed = Vec::new();
+        for id in [2, 0, 3] {
+            let report = event_dispatcher()
+                .dispatch_and_wait(TowerEvent { id })
+                .await;
+            failed.extend(report.failed().to_vec());
+        }
+        assert_eq!(TOTAL.load(Ordering::SeqCst), 5);
+        assert_eq!(CALLS.load(Ordering::SeqCst), 3);
+
+        // The error of the service fails the handler
+        assert_eq!(failed, [handle.handler_id().clone()]);
+
+        let mut service = HandlerService::new(crate::handler_fn(|_| Box::pin(async {})));
+        let event = DispatchedEvent::new("{}".to_string(), "tower".to_string());
+        let outcome = service.ready().await.unwrap().call(event).await;
+        assert_eq!(outcome, Ok(Outcome::Continue));
+    }
+}
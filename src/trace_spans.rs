@@ -0,0 +1,211 @@
+use crate::{dispatched_event::DispatchedEvent, event::Outcome, handler_id::HandlerId};
+use std::{future::Future, time::Duration};
+
+/// The span of the dispatching of an event. It crosses the queue beside the
+/// event, and is a child of the span the event was dispatched in
+#[derive(Debug, Clone)]
+pub(crate) struct DispatchSpan {
+    #[cfg(feature = "tracing")]
+    span: tracing::Span,
+}
+
+impl DispatchSpan {
+    /// Opens the span in the current span
+    #[cfg(feature = "tracing")]
+    pub(crate) fn open(event: &DispatchedEvent) -> Self {
+        let span = tracing::info_span!(
+            target: "orsomafo",
+            "dispatch",
+            event = event.name_ref(),
+            event_id = %event.id(),
+            sequence = event.sequence(),
+            correlation_id = %event.correlation_id(),
+        );
+        Self { span }
+    }
+
+    #[cfg(not(feature = "tracing"))]
+    pub(crate) fn open(_event: &DispatchedEvent) -> Self {
+        Self {}
+    }
+}
+
+/// The span of a call of a handler, a child of the span of the dispatching.
+/// The outcome and the duration of the call are recorded once it ended
+pub(crate) struct HandlerSpan {
+    #[cfg(feature = "tracing")]
+    span: tracing::Span,
+}
+
+impl HandlerSpan {
+    #[cfg(feature = "tracing")]
+    pub(crate) fn new(
+        dispatch: &DispatchSpan,
+        event: &DispatchedEvent,
+        handler_id: &HandlerId,
+    ) -> Self {
+        let span = tracing::info_span!(
+            target: "orsomafo",
+            parent: &dispatch.span,
+            "handle",
+            event = event.name_ref(),
+            handler = %handler_id,
+            outcome = tracing::field::Empty,
+            duration_ms = tracing::field::Empty,
+        );
+        Self { span }
+    }
+
+    #[cfg(not(feature = "tracing"))]
+    pub(crate) fn new(
+        _dispatch: &DispatchSpan,
+        _event: &DispatchedEvent,
+        _handler_id: &HandlerId,
+    ) -> Self {
+        Self {}
+    }
+
+    /// Runs the call of the handler in the span
+    #[cfg(feature = "tracing")]
+    pub(crate) fn instrument<F: Future<Output = Outcome>>(
+        &self,
+        future: F,
+    ) -> impl Future<Output = Outcome> {
+        use tracing::Instrument;
+
+        future.instrument(self.span.clone())
+    }
+
+    #[cfg(not(feature = "tracing"))]
+    pub(crate) fn instrument<F: Future<Output = Outcome>>(
+        &self,
+        future: F,
+    ) -> impl Future<Output = Outcome> {
+        future
+    }
+
+    /// Records how the call ended: `continue`, `stop`, `failed` or `skipped`
+    #[cfg(feature = "tracing")]
+    pub(crate) fn record(&self, outcome: &str, took: Duration) {
+        self.span.record("outcome", outcome);
+        self.span.record(
+            "duration_ms",
+            u64::try_from(took.as_millis()).unwrap_or(u64::MAX),
+        );
+    }
+
+    #[cfg(not(feature = "tracing"))]
+    pub(crate) fn record(&self, _outcome: &str, _took: Duration) {}
+}
+
+#[cfg(feature = "tracing")]
+mod test {
+    #[tokio::test]
+    async fn test_dispatch_spans() {
+        use crate::{event_dispatcher, Dispatchable};
+        use std::sync::{
+            atomic::{AtomicU64, Ordering},
+            Arc, Mutex,
+        };
+        use tracing::{span, Instrument, Metadata, Subscriber};
+
+        // The ID, the name and the parent of a span
+        type RecordedSpan = (u64, &'static str, Option<u64>);
+
+        // The ID of a span, with the name and the value of a field recorded later
+        type RecordedField = (u64, &'static str, String);
+
+        // Records every span
+        #[derive(Default)]
+        struct Recorder {
+            next_id: AtomicU64,
+            spans: Arc<Mutex<Vec<RecordedSpan>>>,
+            fields: Arc<Mutex<Vec<RecordedField>>>,
+            entered: Mutex<Vec<u64>>,
+        }
+
+        struct Fields<'a>(u64, &'a Mutex<Vec<RecordedField>>);
+
+        impl tracing::field::Visit for Fields<'_> {
+            fn record_debug(&mut self, field: &tracing::field::Field, value: &dyn std::fmt::Debug) {
+                self.1
+                    .lock()
+                    .unwrap()
+                    .push((self.0, field.name(), format!("{:?}", value)));
+            }
+        }
+
+        impl Subscriber for Recorder {
+            fn enabled(&self, _: &Metadata<'_>) -> bool {
+                true
+            }
+
+            fn new_span(&self, attributes: &span::Attributes<'_>) -> span::Id {
+                let id = self.next_id.fetch_add(1, Ordering::SeqCst) + 1;
+                let parent = match attributes.parent() {
+                    Some(parent) => Some(parent.into_u64()),
+                    None if attributes.is_contextual() => {
+                        self.entered.lock().unwrap().last().copied()
+                    }
+                    None => None,
+                };
+                self.spans
+                    .lock()
+                    .unwrap()
+                    .push((id, attributes.metadata().name(), parent));
+                span::Id::from_u64(id)
+            }
+
+            fn record(&self, span: &span::Id, values: &span::Record<'_>) {
+                values.record(&mut Fields(span.into_u64(), &self.fields));
+            }
+
+            fn record_follows_from(&self, _: &span::Id, _: &span::Id) {}
+
+            fn event(&self, _: &tracing::Event<'_>) {}
+
+            fn enter(&self, span: &span::Id) {
+                self.entered.lock().unwrap().push(span.into_u64());
+            }
+
+            fn exit(&self, _: &span::Id) {
+                self.entered.lock().unwrap().pop();
+            }
+        }
+
+        #[derive(Clone, serde::Serialize, serde::Deserialize)]
+        struct TracedEvent;
+        impl Dispatchable for TracedEvent {}
+
+        let _handle = TracedEvent::subscribe_fn(|_| Box::pin(async {})).await;
+
+        let recorder = Recorder::default();
+        let spans = recorder.spans.clone();
+        let fields = recorder.fields.clone();
+        let _guard = tracing::subscriber::set_default(recorder);
+
+        async {
+            event_dispatcher().dispatch_and_wait(TracedEvent).await;
+        }
+        .instrument(tracing::info_span!("request"))
+        .await;
+
+        let spans = spans.lock().unwrap().clone();
+        let find = |name: &str| *spans.iter().find(|span| span.1 == name).unwrap();
+        let request = find("request");
+        let dispatch = find("dispatch");
+        let handle = find("handle");
+        assert_eq!(dispatch.2, Some(request.0));
+        assert_eq!(handle.2, Some(dispatch.0));
+
+        let fields = fields.lock().unwrap().clone();
+        let recorded = |name: &str| {
+            fields
+                .iter()
+                .find(|field| field.0 == handle.0 && field.1 == name)
+                .map(|field| field.2.clone())
+        };
+        assert_eq!(recorded("outcome").as_deref(), Some("\"continue\""));
+        assert!(recorded("duration_ms").is_some());
+    }
+}
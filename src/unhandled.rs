@@ -0,0 +1,107 @@
+use crate::{
+    dispatched_event::DispatchedEvent,
+    event::EventHandler,
+    event_listener::{panic_message, LOG_TITLE},
+    handler_id::HandlerId,
+    stats,
+};
+use futures::FutureExt;
+use std::{
+    collections::HashMap,
+    panic::AssertUnwindSafe,
+    sync::{Arc, Mutex, PoisonError},
+};
+
+/// What to do with an event that has no subscribers
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum UnhandledPolicy {
+    /// Only count the event
+    #[default]
+    Ignore,
+    /// Count the event and log a warning
+    Warn,
+    /// Count the event and refuse to dispatch it. `try_dispatch` and `try_dispatch_and_wait`
+    /// return `Error::NoSubscribers`. Events whose handlers all filter them out are refused too
+    Reject,
+}
+
+/// Keeps track of the events that were not handled by any handler
+#[derive(Default)]
+pub(crate) struct Unhandled {
+    policy: UnhandledPolicy,
+    fallback: Option<Arc<dyn EventHandler>>,
+    counts: Mutex<HashMap<String, u64>>,
+}
+
+impl std::fmt::Debug for Unhandled {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Unhandled")
+            .field("policy", &self.policy)
+            .field("fallback", &self.fallback.as_ref().map(|h| h.handler_id()))
+            .field("counts", &self.counts)
+            .finish()
+    }
+}
+
+impl Unhandled {
+    pub(crate) fn new(policy: UnhandledPolicy, fallback: Option<Arc<dyn EventHandler>>) -> Self {
+        Self {
+            policy,
+            fallback,
+            counts: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub(crate) fn policy(&self) -> UnhandledPolicy {
+        self.policy
+    }
+
+    /// Counts the event and passes it to the fallback handler.
+    /// Returns the fallback handler with the panic message or its error when it fails
+    pub(crate) async fn handle(&self, event: DispatchedEvent) -> Result<(), (HandlerId, String)> {
+        self.count(event.name_ref());
+
+        let Some(fallback) = &self.fallback else {
+            return Ok(());
+        };
+        log::trace!(
+            target: LOG_TITLE,
+            "calling fallback handler: {}, for event: {:?}",
+            fallback.handler_id(),
+            event.name_ref()
+        );
+        let failure = match AssertUnwindSafe(fallback.try_handle_event(event))
+            .catch_unwind()
+            .await
+        {
+            Ok(Ok(_)) => return Ok(()),
+            Ok(Err(error)) => error,
+            Err(panic) => panic_message(panic.as_ref()),
+        };
+        // The fallback handler is not a registration, so it has no registration ID
+        Err((HandlerId::new(0, fallback.handler_id(), None), failure))
+    }
+
+    /// Counts an event that was not handled
+    pub(crate) fn count(&self, name: &str) {
+        if self.policy != UnhandledPolicy::Ignore {
+            log::warn!(target: LOG_TITLE, "no handler for event: {:?}", name);
+        }
+
+        let _name = stats::count(
+            &mut self.counts.lock().unwrap_or_else(PoisonError::into_inner),
+            name,
+        );
+        #[cfg(feature = "metrics")]
+        metrics::counter!("orsomafo_events_unhandled_total", "event" => _name.to_string())
+            .increment(1);
+    }
+
+    /// The number of events that were not handled, per event name
+    pub(crate) fn counts(&self) -> HashMap<String, u64> {
+        self.counts
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+}
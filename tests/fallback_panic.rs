@@ -0,0 +1,53 @@
+// The fallback handler is only used when the builder creates the dispatcher,
+// so this test runs in its own binary.
+use orsomafo::{Dispatchable, EventDispatcherBuilder};
+use std::{
+    sync::atomic::{AtomicU64, Ordering},
+    time::Duration,
+};
+
+#[derive(Clone, serde::Deserialize, serde::Serialize)]
+struct OrderPlaced {
+    id: u32,
+}
+
+impl Dispatchable for OrderPlaced {}
+
+#[derive(Clone, serde::Deserialize, serde::Serialize)]
+struct OrderPaid {
+    id: u32,
+}
+
+impl Dispatchable for OrderPaid {}
+
+#[tokio::test]
+async fn test_panicking_fallback_keeps_the_listener_running() {
+    static PAID: AtomicU64 = AtomicU64::new(0);
+
+    let dispatcher = EventDispatcherBuilder::new()
+        .fallback_fn(|_| Box::pin(async { panic!("the fallback fails") }))
+        .listen_fn::<OrderPaid>(|_| {
+            Box::pin(async {
+                PAID.fetch_add(1, Ordering::SeqCst);
+            })
+        })
+        .build()
+        .await;
+
+    // Nothing handles the first event, so it goes to the fallback
+    dispatcher.dispatch(OrderPlaced { id: 1 });
+    dispatcher.dispatch(OrderPaid { id: 1 });
+
+    for _ in 0..100 {
+        if PAID.load(Ordering::SeqCst) == 1 {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    assert_eq!(PAID.load(Ordering::SeqCst), 1);
+
+    let letters = dispatcher.dead_letters();
+    assert_eq!(letters.len(), 1);
+    assert_eq!(letters[0].event().name(), OrderPlaced::event());
+    assert_eq!(letters[0].error(), "the fallback fails");
+}
@@ -0,0 +1,58 @@
+// The hooks of the dispatcher are only used when the builder creates the
+// dispatcher, so this test runs in its own binary.
+use orsomafo::{handler_fn, Dispatchable, EventDispatcherBuilder, EventHandler, Lifetime};
+use std::{sync::Mutex, time::Duration};
+
+#[derive(Clone, serde::Deserialize, serde::Serialize)]
+struct OrderPlaced {
+    id: u32,
+}
+
+impl Dispatchable for OrderPlaced {}
+
+static CALLS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+fn record(call: &str) {
+    CALLS.lock().unwrap().push(call.to_string());
+}
+
+#[tokio::test]
+async fn test_hooks_are_called_in_order() {
+    let dispatcher = EventDispatcherBuilder::new()
+        .before_dispatch(|_| panic!("the hook fails"))
+        .before_dispatch(|event| record(&format!("before_dispatch {}", event.name_ref())))
+        .before_handle(|_, _| record("before_handle"))
+        .after_handle(|_, _, took| {
+            assert!(took >= Duration::from_millis(5));
+            record("after_handle")
+        })
+        .on_error(|_, _, error| record(&format!("on_error {}", error)))
+        .on_handler_removed(|_| record("on_removed"))
+        .build()
+        .await;
+
+    let handle = OrderPlaced::subscribe_with(
+        handler_fn(|_| {
+            Box::pin(async {
+                tokio::time::sleep(Duration::from_millis(5)).await;
+                panic!("the handler fails")
+            })
+        })
+        .with_lifetime(Lifetime::times(1)),
+    )
+    .await;
+
+    // The panicking hook is logged and the next hooks are still called
+    let report = dispatcher.dispatch_and_wait(OrderPlaced { id: 1 }).await;
+    assert_eq!(report.failed(), &[handle.handler_id().clone()]);
+    assert_eq!(
+        *CALLS.lock().unwrap(),
+        [
+            format!("before_dispatch {}", OrderPlaced::event()),
+            "before_handle".to_string(),
+            "after_handle".to_string(),
+            "on_error the handler fails".to_string(),
+            "on_removed".to_string(),
+        ]
+    );
+}
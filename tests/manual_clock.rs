@@ -0,0 +1,43 @@
+// The clock of the dispatcher is only used when the builder creates the
+// dispatcher, so this test runs in its own binary.
+use chrono::{Duration, TimeZone, Utc};
+use orsomafo::{Dispatchable, DispatchedEvent, EventDispatcherBuilder, ManualClock};
+use std::sync::{Arc, Mutex};
+
+#[derive(Clone, serde::Deserialize, serde::Serialize)]
+struct OrderPlaced {
+    id: u32,
+}
+
+impl Dispatchable for OrderPlaced {}
+
+#[tokio::test]
+async fn test_dispatcher_uses_the_manual_clock() {
+    static RECEIVED: Mutex<Option<DispatchedEvent>> = Mutex::new(None);
+
+    let start = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+    let clock = Arc::new(ManualClock::new(start));
+
+    let dispatcher = EventDispatcherBuilder::new()
+        .clock(clock.clone())
+        .listen_fn::<OrderPlaced>(|event| {
+            Box::pin(async move {
+                *RECEIVED.lock().unwrap() = Some(event);
+            })
+        })
+        .build()
+        .await;
+
+    dispatcher.dispatch_and_wait(OrderPlaced { id: 1 }).await;
+    let received = RECEIVED.lock().unwrap().take().unwrap();
+    assert_eq!(received.occurred_at(), start);
+    assert_eq!(received.dispatched_at(), Some(start));
+    assert_eq!(received.handled_at(), Some(start));
+
+    clock.advance(Duration::milliseconds(250));
+    let later = start + Duration::milliseconds(250);
+    dispatcher.dispatch_and_wait(OrderPlaced { id: 2 }).await;
+    let received = RECEIVED.lock().unwrap().take().unwrap();
+    assert_eq!(received.occurred_at(), later);
+    assert_eq!(received.dispatched_at(), Some(later));
+}
@@ -0,0 +1,107 @@
+// The middlewares of the dispatcher are only used when the builder creates the
+// dispatcher, so this test runs in its own binary.
+use orsomafo::{
+    handler_fn, Dispatchable, DispatchedEvent, EventDispatcherBuilder, EventHandler, HandlerId,
+    Lifetime, Middleware, Next, Outcome,
+};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+#[derive(Clone, serde::Deserialize, serde::Serialize)]
+struct OrderPlaced {
+    id: u32,
+}
+
+impl Dispatchable for OrderPlaced {}
+
+// Reads like an `OrderPlaced`, so that `SkipOdd` can check its ID
+#[derive(Clone, serde::Deserialize, serde::Serialize)]
+struct OrderStopped {
+    id: u32,
+}
+
+impl Dispatchable for OrderStopped {}
+
+struct SkipOdd;
+
+#[orsomafo::async_trait]
+impl Middleware for SkipOdd {
+    async fn on_handle(
+        &self,
+        event: DispatchedEvent,
+        _handler: &HandlerId,
+        next: Next<'_>,
+    ) -> Outcome {
+        let order: OrderPlaced = event.the_event().unwrap();
+        if order.id == 0 {
+            return Outcome::Stop;
+        }
+        if order.id % 2 == 1 {
+            return Outcome::Continue;
+        }
+        next.run(event).await
+    }
+}
+
+#[tokio::test]
+async fn test_skipped_handlers_are_not_called() {
+    static CALLED: AtomicU64 = AtomicU64::new(0);
+
+    let dispatcher = EventDispatcherBuilder::new()
+        .middleware(SkipOdd)
+        .build()
+        .await;
+
+    let handle = OrderPlaced::subscribe_with(
+        handler_fn(|_| {
+            Box::pin(async {
+                CALLED.fetch_add(1, Ordering::SeqCst);
+            })
+        })
+        .with_lifetime(Lifetime::times(1)),
+    )
+    .await;
+
+    // The middleware skips the handler, so the call is not used up
+    let skipped = dispatcher.dispatch_and_wait(OrderPlaced { id: 1 }).await;
+    assert!(skipped.handled_by().is_empty());
+    assert_eq!(skipped.skipped(), &[handle.handler_id().clone()]);
+    assert!(skipped.stopped_before().is_none());
+    assert_eq!(CALLED.load(Ordering::SeqCst), 0);
+
+    let registry = dispatcher.registry();
+    let handlers = registry.event(&OrderPlaced::event()).unwrap().handlers();
+    assert_eq!(handlers[0].remaining_calls(), Some(1));
+
+    let handled = dispatcher.dispatch_and_wait(OrderPlaced { id: 2 }).await;
+    assert_eq!(handled.handled_by(), &[handle.handler_id().clone()]);
+    assert_eq!(CALLED.load(Ordering::SeqCst), 1);
+    assert!(dispatcher.registry().event(&OrderPlaced::event()).is_none());
+}
+
+#[tokio::test]
+async fn test_middleware_stops_before_the_handler() {
+    static CALLED: AtomicU64 = AtomicU64::new(0);
+
+    let dispatcher = EventDispatcherBuilder::new()
+        .middleware(SkipOdd)
+        .build()
+        .await;
+
+    let count = || {
+        handler_fn(|_| {
+            Box::pin(async {
+                CALLED.fetch_add(1, Ordering::SeqCst);
+            })
+        })
+    };
+    let first = OrderStopped::subscribe_with(count().with_priority(1)).await;
+    let _second = OrderStopped::subscribe_with(count()).await;
+
+    // The middleware stops the event before the first handler is called
+    let report = dispatcher.dispatch_and_wait(OrderStopped { id: 0 }).await;
+    assert!(report.handled_by().is_empty());
+    assert!(report.stopped_by().is_none());
+    assert_eq!(report.stopped_before(), Some(first.handler_id()));
+    assert_eq!(report.skipped(), &[first.handler_id().clone()]);
+    assert_eq!(CALLED.load(Ordering::SeqCst), 0);
+}
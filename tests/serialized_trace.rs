@@ -0,0 +1,69 @@
+// Queued events are handled by the listener of the runtime that created the
+// dispatcher, so this test runs in its own binary.
+#![cfg(feature = "opentelemetry")]
+
+use opentelemetry::{
+    global,
+    trace::{TraceContextExt, Tracer, TracerProvider},
+    Context,
+};
+use opentelemetry_sdk::{
+    propagation::TraceContextPropagator,
+    trace::{InMemorySpanExporter, SdkTracerProvider},
+};
+use orsomafo::{Dispatchable, EventDispatcherBuilder};
+use std::time::Duration;
+
+#[derive(Clone, serde::Deserialize, serde::Serialize)]
+struct OrderPlaced {
+    id: u32,
+}
+
+impl Dispatchable for OrderPlaced {}
+
+#[tokio::test]
+async fn test_serialized_events_carry_the_trace_context() {
+    let exporter = InMemorySpanExporter::default();
+    let provider = SdkTracerProvider::builder()
+        .with_simple_exporter(exporter.clone())
+        .build();
+    global::set_tracer_provider(provider.clone());
+    global::set_text_map_propagator(TraceContextPropagator::new());
+
+    let dispatcher = EventDispatcherBuilder::new()
+        .listen_fn::<OrderPlaced>(|_| {
+            Box::pin(async {
+                global::tracer("handler").in_span("handle order", |_| {});
+            })
+        })
+        .build()
+        .await;
+
+    let publish = provider.tracer("test").start("publish");
+    let publish_context = Context::current_with_span(publish);
+    let publish_span = publish_context.span().span_context().clone();
+    let json = {
+        let _attached = publish_context.clone().attach();
+        OrderPlaced { id: 1 }.serialize_event()
+    };
+    publish_context.span().end();
+    assert!(dispatcher.try_dispatch_json(&json).is_ok());
+
+    let mut handled = None;
+    for _ in 0..100 {
+        provider.force_flush().unwrap();
+        handled = exporter
+            .get_finished_spans()
+            .unwrap()
+            .into_iter()
+            .find(|span| span.name == "handle order");
+        if handled.is_some() {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+
+    let handled = handled.unwrap();
+    assert_eq!(handled.span_context.trace_id(), publish_span.trace_id());
+    assert_eq!(handled.parent_span_id, publish_span.span_id());
+}